@@ -0,0 +1,615 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use pixel_loop::canvas::Canvas;
+
+use crate::font::Font;
+use crate::glyph::{FloatingTetromino, Glyph};
+use crate::laps::LapList;
+use crate::layout::{Layout, Placement, GLYPH_HEIGHT};
+use crate::mode::{Lap, Mode, Phase};
+use crate::tetromino::{
+    AnimationPolicy, Board, Colorscheme, DigitBoard, Physics, Rotation, Shape, Transition,
+};
+
+// Every line of the time string becomes a row of glyphs. Characters without
+// a glyph (eg. from a custom clock format) are skipped.
+fn time_string_to_rows<T: AsRef<str>>(time_string: T) -> Vec<Vec<Glyph>> {
+    time_string
+        .as_ref()
+        .lines()
+        .map(|line| line.chars().filter_map(Glyph::from_char).collect())
+        .collect()
+}
+
+/// Events emitted by [`Clock::update`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClockEvent {
+    /// The countdown reached zero
+    Finished,
+    /// The pomodoro switched to the given phase
+    PhaseChanged(Phase),
+    /// The interval started the segment with the given index
+    SegmentStarted(usize),
+}
+
+// Space between the digits and the pomodoro progress indicator or the lap
+// list below them
+const FOOTER_GAP: i64 = 2;
+// Each completed work phase is shown as an O tetromino
+const PROGRESS_PIECE_SIZE: i64 = 2;
+
+/// A self contained tetromino clock widget.
+///
+/// The clock renders the time string of its [`Mode`] (eg. `12:34:56`) as
+/// tetromino glyphs. Time strings spanning multiple lines are displayed as
+/// stacked rows. It can be placed anywhere on a canvas using
+/// `set_position` (or `center_on` to put it in the middle of a given area).
+/// All coordinates are canvas pixels. Each tetromino cell is rendered as a
+/// square of `scale` x `scale` pixels.
+///
+/// In pomodoro mode every phase can use its own colorscheme and the
+/// completed work phases are shown as small tetrominos below the digits.
+pub struct Clock {
+    digit_boards: Vec<DigitBoard>,
+    current_rows: Vec<Vec<Glyph>>,
+    current_progress: Option<(Phase, u32)>,
+    seperator_boards: Vec<Board>,
+    progress_board: Option<Board>,
+    lap_list: Option<LapList>,
+    // Boards of a previous layout, whose tetrominos are falling out of the
+    // canvas
+    leaving_boards: Vec<Board>,
+    layout: Layout,
+    scale: i64,
+    x_start: i64,
+    y_stop: i64,
+    last_tick: i64,
+    tick: u32,
+    animation: AnimationPolicy,
+    physics: Physics,
+    diffing: bool,
+    transition: Transition,
+    font: Arc<Font>,
+    colorscheme: Colorscheme,
+    phase_colorschemes: HashMap<Phase, Colorscheme>,
+    mode: Mode,
+    finished: bool,
+}
+
+impl Clock {
+    /// Height of a single row of glyphs in cells
+    pub const HEIGHT: i64 = GLYPH_HEIGHT;
+
+    pub fn new(mode: Mode, colorscheme: Colorscheme) -> Self {
+        Self {
+            digit_boards: vec![],
+            current_rows: vec![],
+            current_progress: None,
+            seperator_boards: vec![],
+            progress_board: None,
+            lap_list: None,
+            leaving_boards: vec![],
+            layout: Layout::default(),
+            scale: 1,
+            x_start: 0,
+            y_stop: 0,
+            last_tick: 0,
+            tick: 1,
+            animation: AnimationPolicy::default(),
+            physics: Physics::default(),
+            diffing: false,
+            transition: Transition::default(),
+            font: Arc::default(),
+            mode,
+            colorscheme,
+            phase_colorschemes: HashMap::new(),
+            finished: false,
+        }
+    }
+
+    pub fn mode(&self) -> &Mode {
+        &self.mode
+    }
+
+    /// The colorscheme currently in use, which depends on the phase of a
+    /// pomodoro
+    pub fn colorscheme(&self) -> Colorscheme {
+        self.mode
+            .phase()
+            .and_then(|phase| self.phase_colorschemes.get(&phase))
+            .copied()
+            .unwrap_or(self.colorscheme)
+    }
+
+    /// Use a different colorscheme during the given pomodoro phase
+    pub fn set_phase_colorscheme(&mut self, phase: Phase, colorscheme: Colorscheme) {
+        self.phase_colorschemes.insert(phase, colorscheme);
+    }
+
+    /// Only refresh the digits every `seconds` seconds (aligned to the second
+    /// boundary) instead of every second, eg. for slow terminals.
+    pub fn set_tick(&mut self, seconds: u32) {
+        self.tick = seconds.max(1);
+    }
+
+    /// Change how the tetrominos of the glyphs are brought into place.
+    /// Takes effect with the next call to `set_position` or `center_on`.
+    /// Fractions of a second are always placed instantly.
+    pub fn set_animation_policy(&mut self, animation: AnimationPolicy) {
+        self.animation = animation;
+    }
+
+    /// Change the fall speed, spawn interval and gravity of the tetrominos.
+    /// Takes effect with the next call to `set_position` or `center_on`.
+    pub fn set_physics(&mut self, physics: Physics) {
+        self.physics = physics;
+    }
+
+    /// Only exchange the tetrominos which differ when a glyph changes (eg.
+    /// from 8 to 9), instead of rebuilding the whole glyph. Takes effect
+    /// with the next call to `set_position` or `center_on`.
+    pub fn set_diffing(&mut self, diffing: bool) {
+        self.diffing = diffing;
+    }
+
+    /// Change how the previous glyph leaves, when a glyph changes. Takes
+    /// effect with the next call to `set_position` or `center_on`.
+    pub fn set_transition(&mut self, transition: Transition) {
+        self.transition = transition;
+    }
+
+    /// Build the digits and letters from the tetrominos of the given font.
+    /// Takes effect with the next call to `set_position` or `center_on`.
+    pub fn set_font(&mut self, font: Font) {
+        self.font = Arc::new(font);
+    }
+
+    // Fractions of a second are refreshed as soon as they change
+    fn current_tick(&self) -> i64 {
+        self.mode
+            .current_fraction()
+            .unwrap_or_else(|| self.mode.current_second().div_euclid(self.tick as i64))
+    }
+
+    /// Change the horizontal space between glyphs. Takes effect with the
+    /// next call to `set_position` or `center_on`.
+    pub fn set_spacing(&mut self, spacing: i64) {
+        self.layout = Layout::new(spacing).with_row_spacing(self.layout.row_spacing());
+    }
+
+    /// Change the vertical space between rows. Takes effect with the next
+    /// call to `set_position` or `center_on`.
+    pub fn set_row_spacing(&mut self, row_spacing: i64) {
+        self.layout = self.layout.with_row_spacing(row_spacing);
+    }
+
+    /// Change the size of a tetromino cell in pixels. Takes effect with the
+    /// next call to `set_position` or `center_on`.
+    pub fn set_scale(&mut self, scale: i64) {
+        self.scale = scale.max(1);
+    }
+
+    pub fn scale(&self) -> i64 {
+        self.scale
+    }
+
+    /// The largest scale at which the clock (with a small margin) still fits
+    /// into an area of the given size
+    pub fn fit_scale(&self, width: u32, height: u32) -> i64 {
+        let rows = time_string_to_rows(self.mode.get_timestring());
+        let cells_x = self.layout.rows_width(&rows) + 4;
+        let cells_y = self.layout.rows_height(rows.len()) + self.footer_height() + 4;
+        (width as i64 / cells_x).min(height as i64 / cells_y).max(1)
+    }
+
+    /// Width of the clock in pixels for the currently displayed time
+    pub fn width(&self) -> i64 {
+        self.layout
+            .rows_width(&time_string_to_rows(self.mode.get_timestring()))
+            * self.scale
+    }
+
+    /// Height of the clock in pixels for the currently displayed time
+    pub fn height(&self) -> i64 {
+        let rows = time_string_to_rows(self.mode.get_timestring()).len();
+        (self.layout.rows_height(rows) + self.footer_height()) * self.scale
+    }
+
+    // Phase and completed work phases of a pomodoro
+    fn progress(&self) -> Option<(Phase, u32)> {
+        match &self.mode {
+            Mode::Pomodoro(pomodoro) => Some((pomodoro.phase(), pomodoro.completed())),
+            _ => None,
+        }
+    }
+
+    // Cells needed below the digits for the pomodoro progress indicator or
+    // the lap list of the stopwatch
+    fn footer_height(&self) -> i64 {
+        match self.mode {
+            Mode::Pomodoro(_) => FOOTER_GAP + PROGRESS_PIECE_SIZE,
+            Mode::Stopwatch(..) => FOOTER_GAP + LapList::HEIGHT,
+            _ => 0,
+        }
+    }
+
+    /// Center the clock within an area of the given size
+    pub fn center_on(&mut self, width: u32, height: u32) {
+        let x_start = (width as i64 - self.width()) / 2;
+        let y_stop = (height as i64 + self.height()) / 2 - self.footer_height() * self.scale;
+        self.set_position(x_start, y_stop);
+    }
+
+    /// Place the clock with its left edge at `x_start` and the bottom of the
+    /// digits (of the last row) resting on `y_stop`. The progress indicator
+    /// of a pomodoro and the laps of a stopwatch are placed below `y_stop`.
+    pub fn set_position(&mut self, x_start: i64, y_stop: i64) {
+        self.x_start = x_start;
+        self.y_stop = y_stop;
+        self.layout(time_string_to_rows(self.mode.get_timestring()));
+    }
+
+    fn layout(&mut self, rows: Vec<Vec<Glyph>>) {
+        let colorscheme = self.colorscheme();
+        let scale = self.scale;
+        // Boards work in cells, while the clock is positioned in pixels
+        let y_start = self.y_stop.div_euclid(scale) - self.layout.rows_height(rows.len());
+        self.digit_boards = vec![];
+        self.seperator_boards = vec![];
+
+        // Digits following the dot of a stopwatch are fractions of a
+        // second, which change too fast to be dropped piece by piece
+        let has_fraction = self.mode.current_fraction().is_some();
+        let mut fraction_row = None;
+        let physics = self.animation.physics(self.physics);
+        for Placement { glyph, x, y } in self.layout.place_rows(&rows, 0, y_start) {
+            let x = self.x_start + x * scale;
+            let y_stop = y + GLYPH_HEIGHT;
+            if has_fraction && glyph == Glyph::Dot {
+                fraction_row = Some(y);
+            }
+            if glyph.is_animated() {
+                let position = self.digit_boards.len();
+                self.digit_boards.push(
+                    DigitBoard::new(position, x, y_stop, colorscheme, glyph)
+                        .with_scale(scale)
+                        .with_physics(self.physics)
+                        .with_diffing(self.diffing)
+                        .with_transition(self.transition)
+                        .with_font(Arc::clone(&self.font))
+                        .with_animation_policy(if fraction_row == Some(y) {
+                            AnimationPolicy::Instant
+                        } else {
+                            self.animation
+                        }),
+                );
+                continue;
+            }
+
+            // Each piece of a separator falls separately and stays in the
+            // air on its own. All of them travel the same distance, so they
+            // arrive together.
+            for FloatingTetromino {
+                shape,
+                rotation,
+                dx,
+                lift,
+            } in glyph.floating_tetrominos()
+            {
+                let color = colorscheme.apply(shape, glyph, 0);
+                let mut board = Board::new(x, -lift, y_stop - lift)
+                    .with_scale(scale)
+                    .with_physics(physics);
+                if self.animation == AnimationPolicy::Instant {
+                    board.place_tetromino(dx, color, shape, rotation);
+                } else {
+                    board.add_tetromino(dx, 0, color, shape, rotation);
+                }
+                self.seperator_boards.push(board);
+            }
+        }
+
+        self.progress_board = self.layout_progress(&rows);
+        self.lap_list = self.layout_laps(&rows);
+        self.current_rows = rows;
+        self.current_progress = self.progress();
+        self.last_tick = self.current_tick();
+        self.update_indicators();
+    }
+
+    // One slot per work phase until the long break, centered below the
+    // digits. The pieces of completed work phases drop into their slot.
+    fn layout_progress(&self, rows: &[Vec<Glyph>]) -> Option<Board> {
+        let Mode::Pomodoro(pomodoro) = &self.mode else {
+            return None;
+        };
+        let scale = self.scale;
+        let slot = PROGRESS_PIECE_SIZE + 1;
+        let width = pomodoro.cycles as i64 * slot - 1;
+        let x = self.x_start + (self.layout.rows_width(rows) - width) / 2 * scale;
+        let y_stop = self.y_stop.div_euclid(scale) + FOOTER_GAP + PROGRESS_PIECE_SIZE;
+
+        let mut board = Board::new(x, y_stop - PROGRESS_PIECE_SIZE, y_stop)
+            .with_scale(scale)
+            .with_physics(self.animation.physics(self.physics));
+        for i in 0..pomodoro.completed() as usize {
+            let color = self.colorscheme().apply(Shape::O, Glyph::Colon, i);
+            board.add_tetromino(i as i64 * slot, 0, color, Shape::O, Rotation::NoRotation);
+        }
+        Some(board)
+    }
+
+    fn layout_laps(&self, rows: &[Vec<Glyph>]) -> Option<LapList> {
+        let Mode::Stopwatch(..) = self.mode else {
+            return None;
+        };
+        let scale = self.scale;
+        let x_center = self.x_start + self.layout.rows_width(rows) * scale / 2;
+        let y_start = (self.y_stop.div_euclid(scale) + FOOTER_GAP) * scale;
+        let color = self.colorscheme().apply(Shape::O, Glyph::Colon, 0);
+        Some(LapList::new(x_center, y_start, color).with_scale(scale))
+    }
+
+    // Let all tetrominos currently displayed fall out of the canvas. The
+    // boards are emptied, so a new layout is required afterwards.
+    fn fall_out(&mut self) {
+        let boards = self
+            .digit_boards
+            .drain(..)
+            .map(DigitBoard::into_board)
+            .chain(self.seperator_boards.drain(..))
+            .chain(self.progress_board.take());
+        for mut board in boards {
+            board.initiate_fall_out();
+            self.leaving_boards.push(board);
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.mode.is_paused()
+    }
+
+    /// Pause or resume the stopwatch/countdown/pomodoro. Held tetrominos are dimmed
+    /// while paused.
+    pub fn toggle_pause(&mut self) {
+        self.mode.toggle_pause();
+        self.update_indicators();
+        self.refresh_time();
+    }
+
+    /// Reset the stopwatch/countdown to its initial value. A pomodoro starts
+    /// over with the first work phase.
+    pub fn reset(&mut self) {
+        self.mode.reset();
+        self.finished = false;
+        self.update_indicators();
+        self.refresh_time();
+    }
+
+    /// Whether the countdown has reached zero. Once finished all digits
+    /// flash until the clock is reset.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn update_indicators(&mut self) {
+        let paused = self.mode.is_paused();
+        let finished = self.finished;
+        for board in self.digit_boards.iter_mut() {
+            board.set_dimmed(paused);
+            board.set_flashing(finished);
+        }
+        for board in self
+            .seperator_boards
+            .iter_mut()
+            .chain(self.progress_board.as_mut())
+        {
+            board.set_dimmed(paused);
+            board.set_flashing(finished);
+        }
+    }
+
+    fn refresh_time(&mut self) {
+        self.update_time(time_string_to_rows(self.mode.get_timestring()));
+        self.last_tick = self.current_tick();
+    }
+
+    fn update_time(&mut self, rows: Vec<Vec<Glyph>>) {
+        // A changed structure (eg. a countdown switching from days to
+        // seconds) or a new pomodoro phase needs a new layout. Otherwise
+        // only changed glyphs are replaced.
+        let same_structure = self.progress() == self.current_progress
+            && rows.len() == self.current_rows.len()
+            && rows.iter().zip(self.current_rows.iter()).all(|(new, old)| {
+                new.len() == old.len()
+                    && new
+                        .iter()
+                        .zip(old.iter())
+                        .all(|(new, old)| (new.is_animated() && old.is_animated()) || new == old)
+            });
+        if !same_structure {
+            self.layout(rows);
+            return;
+        }
+
+        let animated = rows
+            .iter()
+            .flatten()
+            .zip(self.current_rows.iter().flatten())
+            .filter(|(new, _)| new.is_animated());
+        for (board, (new, old)) in self.digit_boards.iter_mut().zip(animated) {
+            if new != old {
+                board.set_glyph(*new);
+            }
+        }
+        self.current_rows = rows;
+    }
+
+    /// Advance the animation by `dt`, the time passed since the last update
+    pub fn update<C: Canvas>(&mut self, canvas: &C, dt: Duration) -> Option<ClockEvent> {
+        let mut event = None;
+        if self.mode.advance() {
+            // Everything falls out at once, to make the start of a new phase
+            // or segment stand out from a simple change of the digits
+            self.fall_out();
+            self.layout(time_string_to_rows(self.mode.get_timestring()));
+            event = self
+                .mode
+                .phase()
+                .map(ClockEvent::PhaseChanged)
+                .or(self.mode.segment().map(ClockEvent::SegmentStarted));
+        }
+
+        // Digits are refreshed before the boards are updated, so a changed
+        // digit starts dropping in the same update the second changed.
+        if self.current_tick() != self.last_tick {
+            self.refresh_time();
+        }
+
+        if !self.finished && self.mode.is_finished() {
+            self.finished = true;
+            self.refresh_time();
+            self.update_indicators();
+            event = Some(ClockEvent::Finished);
+        }
+
+        for board in self.digit_boards.iter_mut() {
+            board.update(canvas, dt);
+        }
+
+        for board in self
+            .seperator_boards
+            .iter_mut()
+            .chain(self.progress_board.as_mut())
+            .chain(self.leaving_boards.iter_mut())
+        {
+            board.update(canvas, dt);
+        }
+        self.leaving_boards.retain(|board| !board.is_empty());
+
+        event
+    }
+
+    /// Record a new lap, if the clock shows a stopwatch
+    pub fn record_lap(&mut self) -> Option<Lap> {
+        self.mode.record_lap()
+    }
+
+    pub fn render<C: Canvas>(&self, canvas: &mut C) {
+        for board in self.digit_boards.iter() {
+            board.render(canvas);
+        }
+
+        if let Some(lap_list) = &self.lap_list {
+            lap_list.render(canvas, &self.mode.laps());
+        }
+
+        for board in self
+            .seperator_boards
+            .iter()
+            .chain(self.progress_board.as_ref())
+            .chain(self.leaving_boards.iter())
+        {
+            board.render(canvas);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use pixel_loop::canvas::InMemoryCanvas;
+    use pixel_loop::color::Color;
+
+    use super::*;
+    use crate::mode::{Interval, Pomodoro, Precision, Segment, Stopwatch};
+    use crate::time;
+
+    // Step the frozen time second by second and collect the events emitted
+    // along the way, together with the second they were emitted in
+    fn events(mode: Mode, seconds: i64) -> Vec<(i64, ClockEvent)> {
+        let canvas = InMemoryCanvas::new(80, 40, &Color::from_rgb(0, 0, 0));
+        let mut clock = Clock::new(mode, Colorscheme::default());
+        (1..=seconds)
+            .filter_map(|second| {
+                time::advance(chrono::Duration::seconds(1));
+                clock
+                    .update(&canvas, Duration::from_secs(1))
+                    .map(|event| (second, event))
+            })
+            .collect()
+    }
+
+    fn start() {
+        time::freeze(Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn fractions_of_a_second_are_placed_instantly() {
+        for (precision, fractions) in [(Precision::Tenths, 1), (Precision::Hundredths, 2)] {
+            start();
+            let canvas = InMemoryCanvas::new(120, 40, &Color::from_rgb(0, 0, 0));
+            let stopwatch = Stopwatch::new().with_precision(precision);
+            let mut clock = Clock::new(Mode::Stopwatch(stopwatch), Colorscheme::default());
+            clock.center_on(canvas.width(), canvas.height());
+
+            clock.update(&canvas, Duration::from_millis(16));
+
+            let (seconds, fraction) = clock
+                .digit_boards
+                .split_at(clock.digit_boards.len() - fractions);
+            assert_eq!(seconds.len(), 6, "{:?}", precision);
+            assert!(
+                fraction.iter().all(DigitBoard::is_settled),
+                "{:?}",
+                precision
+            );
+            assert!(
+                !seconds.iter().any(DigitBoard::is_settled),
+                "{:?}",
+                precision
+            );
+        }
+    }
+
+    #[test]
+    fn pomodoro_emits_phase_changes() {
+        start();
+        let minutes = chrono::Duration::minutes;
+        let pomodoro = Pomodoro::new(minutes(2), minutes(1), minutes(3), 2);
+        assert_eq!(
+            events(Mode::Pomodoro(pomodoro), 10 * 60),
+            [
+                (120, ClockEvent::PhaseChanged(Phase::ShortBreak)),
+                (180, ClockEvent::PhaseChanged(Phase::Work)),
+                (300, ClockEvent::PhaseChanged(Phase::LongBreak)),
+                (480, ClockEvent::PhaseChanged(Phase::Work)),
+                (600, ClockEvent::PhaseChanged(Phase::ShortBreak)),
+            ]
+        );
+    }
+
+    #[test]
+    fn interval_emits_segment_starts_and_finishes() {
+        start();
+        let seconds = chrono::Duration::seconds;
+        let interval = Interval::new(
+            vec![
+                Segment::new("ON", seconds(3)),
+                Segment::new("OFF", seconds(2)),
+            ],
+            2,
+        );
+        assert_eq!(
+            events(Mode::Interval(interval), 15),
+            [
+                (3, ClockEvent::SegmentStarted(1)),
+                (5, ClockEvent::SegmentStarted(0)),
+                (8, ClockEvent::SegmentStarted(1)),
+                (10, ClockEvent::Finished),
+            ]
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use crate::tetromino::{Rotation, Shape};
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Digit {
     Zero,
     One,
@@ -40,7 +40,7 @@ impl From<u32> for Digit {
 
 pub type Animation = Vec<FallingTetromino>;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct FallingTetromino {
     pub shape: Shape,
     pub rotation: Rotation,
@@ -58,11 +58,11 @@ impl FallingTetromino {
     
 }
 
-impl Into<Animation> for Digit {
-    fn into(self) -> Animation {
+impl From<Digit> for Animation {
+    fn from(digit: Digit) -> Self {
         use Shape::*;
         use Rotation::*;
-        match self {
+        match digit {
             Digit::Zero => vec![
                 FallingTetromino::new(J, Degrees90, 4),
                 FallingTetromino::new(I, Degrees90, 0),
@@ -0,0 +1,326 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+
+use crate::digits::{Animation, FallingTetromino};
+use crate::glyph::Glyph;
+use crate::simulator::Bitmap;
+use crate::tetromino::{Rotation, Shape};
+
+/// The tetrominos the animated glyphs (digits and letters) are built from.
+///
+/// The default font is the one built into tetrotime. A font loaded from a
+/// file only needs to list the glyphs it changes, all others are taken from
+/// the built-in font. The file is written in TOML, listing the tetrominos of
+/// every glyph in the order they are dropped:
+///
+/// ```toml
+/// [glyphs]
+/// "1" = [
+///     { shape = "O", dx = 4 },
+///     { shape = "L", rotation = 270, dx = 4 },
+///     { shape = "I", rotation = 90, dx = 5 },
+///     { shape = "J", rotation = 270, dx = 4 },
+///     { shape = "O", dx = 4 },
+/// ]
+/// ```
+///
+/// `shape` is one of L, J, O, T, I, S and Z, `rotation` is given in degrees
+/// (0, 90, 180 or 270, defaults to 0) and `dx` is the column of the
+/// tetromino within the glyph (see [`Shape::cells`]).
+///
+/// The optional `[bitmaps]` table holds the ASCII art every glyph is expected
+/// to settle into (see [`Bitmap`]), which `tetrotime glyph-check` verifies:
+///
+/// ```toml
+/// [bitmaps]
+/// "1" = """
+/// ....##
+/// ....##
+/// ....##
+/// ....##
+/// ....##
+/// ....##
+/// ....##
+/// ....##
+/// ....##
+/// ....##
+/// """
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Font {
+    glyphs: HashMap<Glyph, Animation>,
+    bitmaps: HashMap<Glyph, Bitmap>,
+    listed: Vec<(char, Glyph)>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FontFile {
+    #[serde(default)]
+    glyphs: BTreeMap<String, Vec<TetrominoEntry>>,
+    #[serde(default)]
+    bitmaps: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TetrominoEntry {
+    shape: String,
+    #[serde(default)]
+    rotation: u32,
+    dx: i64,
+}
+
+impl TetrominoEntry {
+    fn to_falling_tetromino(&self) -> Result<FallingTetromino> {
+        let shape = match self.shape.to_ascii_uppercase().as_str() {
+            "L" => Shape::L,
+            "J" => Shape::J,
+            "O" => Shape::O,
+            "T" => Shape::T,
+            "I" => Shape::I,
+            "S" => Shape::S,
+            "Z" => Shape::Z,
+            _ => bail!(
+                "Unknown shape '{}'. Expected one of L, J, O, T, I, S or Z",
+                self.shape
+            ),
+        };
+        let rotation = match self.rotation {
+            0 => Rotation::NoRotation,
+            90 => Rotation::Degrees90,
+            180 => Rotation::Degrees180,
+            270 => Rotation::Degrees270,
+            _ => bail!(
+                "Invalid rotation {}. Expected 0, 90, 180 or 270",
+                self.rotation
+            ),
+        };
+        Ok(FallingTetromino::new(shape, rotation, self.dx))
+    }
+}
+
+impl Font {
+    /// Parse a font from the contents of a TOML font file
+    pub fn from_toml(toml: &str) -> Result<Self> {
+        let file: FontFile = toml::from_str(toml)?;
+        let mut glyphs = HashMap::new();
+        let mut listed = Vec::new();
+        for (key, entries) in file.glyphs {
+            let glyph = Self::parse_glyph(&key)?;
+            let animation = entries
+                .iter()
+                .enumerate()
+                .map(|(n, entry)| {
+                    let tetromino = entry.to_falling_tetromino()?;
+                    let sticks_out = tetromino
+                        .shape
+                        .cells(tetromino.rotation)
+                        .iter()
+                        .any(|&(cx, _)| !(0..glyph.width()).contains(&(tetromino.dx + cx)));
+                    if sticks_out {
+                        bail!("Tetromino {} sticks out of the glyph", n + 1);
+                    }
+                    Ok(tetromino)
+                })
+                .collect::<Result<Animation>>()
+                .with_context(|| format!("Invalid glyph '{}'", key))?;
+            if animation.is_empty() {
+                bail!("Glyph '{}' has no tetrominos", key);
+            }
+            glyphs.insert(glyph, animation);
+            listed.extend(key.chars().map(|c| (c, glyph)));
+        }
+        let mut bitmaps = HashMap::new();
+        for (key, art) in file.bitmaps {
+            let glyph = Self::parse_glyph(&key)?;
+            let bitmap = art
+                .parse()
+                .with_context(|| format!("Invalid bitmap of glyph '{}'", key))?;
+            bitmaps.insert(glyph, bitmap);
+            if !listed.iter().any(|&(_, listed)| listed == glyph) {
+                listed.extend(key.chars().map(|c| (c, glyph)));
+            }
+        }
+        listed.sort_unstable_by_key(|&(c, _)| c);
+        Ok(Self {
+            glyphs,
+            bitmaps,
+            listed,
+        })
+    }
+
+    /// Read a font from a TOML font file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Could not read font file '{}'", path.display()))?;
+        Self::from_toml(&content).with_context(|| format!("Invalid font file '{}'", path.display()))
+    }
+
+    // Only the glyphs built on a `DigitBoard` can be changed
+    fn parse_glyph(key: &str) -> Result<Glyph> {
+        let mut chars = key.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Glyph::from_char(c)
+                .filter(Glyph::is_animated)
+                .ok_or_else(|| anyhow!("The font can not contain the glyph '{}'", key)),
+            _ => bail!("Glyphs must be a single character. Got '{}'", key),
+        }
+    }
+
+    /// The tetrominos the given glyph is built from
+    pub fn animation(&self, glyph: Glyph) -> Animation {
+        self.glyphs
+            .get(&glyph)
+            .cloned()
+            .unwrap_or_else(|| glyph.into())
+    }
+
+    /// Build the given glyph from the tetrominos of the animation
+    pub fn set_animation(&mut self, glyph: Glyph, animation: Animation) {
+        self.glyphs.insert(glyph, animation);
+    }
+
+    /// Format the tetrominos of a glyph as an entry of the `[glyphs]` table of
+    /// a font file
+    pub fn to_toml_entry(key: char, animation: &Animation) -> String {
+        let tetrominos: Vec<String> = animation
+            .iter()
+            .map(|tetromino| {
+                let rotation = match tetromino.rotation {
+                    Rotation::NoRotation => String::new(),
+                    Rotation::Degrees90 => "rotation = 90, ".to_string(),
+                    Rotation::Degrees180 => "rotation = 180, ".to_string(),
+                    Rotation::Degrees270 => "rotation = 270, ".to_string(),
+                };
+                format!(
+                    "    {{ shape = \"{:?}\", {}dx = {} }},\n",
+                    tetromino.shape, rotation, tetromino.dx
+                )
+            })
+            .collect();
+        format!("\"{}\" = [\n{}]", key, tetrominos.concat())
+    }
+
+    /// The bitmap the given glyph is expected to settle into, if the font
+    /// file contains one
+    pub fn bitmap(&self, glyph: Glyph) -> Option<&Bitmap> {
+        self.bitmaps.get(&glyph)
+    }
+
+    /// The glyphs listed in the font file, either with their tetrominos or
+    /// with a bitmap, along with the character they were listed as
+    pub fn listed_glyphs(&self) -> &[(char, Glyph)] {
+        &self.listed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(toml: &str) -> String {
+        format!("{:#}", Font::from_toml(toml).unwrap_err())
+    }
+
+    #[test]
+    fn glyphs_replace_the_built_in_ones() {
+        let font = Font::from_toml(
+            "[glyphs]\n\"1\" = [{ shape = \"i\", rotation = 90, dx = 5 }]\n\
+             [bitmaps]\n\"a\" = \"#\"",
+        )
+        .unwrap();
+        assert_eq!(
+            font.animation(Glyph::from_char('1').unwrap()),
+            vec![FallingTetromino::new(Shape::I, Rotation::Degrees90, 5)]
+        );
+        assert!(font.bitmap(Glyph::from_char('A').unwrap()).is_some());
+        assert_eq!(font.listed_glyphs().len(), 2);
+    }
+
+    #[test]
+    fn unknown_shape() {
+        let message = error("[glyphs]\n\"1\" = [{ shape = \"X\", dx = 0 }]");
+        assert!(message.contains("Unknown shape 'X'"), "{}", message);
+    }
+
+    #[test]
+    fn invalid_rotation() {
+        let message = error("[glyphs]\n\"1\" = [{ shape = \"O\", rotation = 45, dx = 0 }]");
+        assert!(message.contains("Invalid rotation 45"), "{}", message);
+    }
+
+    #[test]
+    fn tetromino_sticking_out() {
+        for dx in [-1, 5] {
+            let message = error(&format!(
+                "[glyphs]\n\"1\" = [{{ shape = \"O\", dx = 4 }}, {{ shape = \"O\", dx = {} }}]",
+                dx
+            ));
+            assert!(
+                message.contains("Invalid glyph '1': Tetromino 2 sticks out of the glyph"),
+                "{}",
+                message
+            );
+        }
+    }
+
+    #[test]
+    fn empty_glyph() {
+        let message = error("[glyphs]\n\"1\" = []");
+        assert!(
+            message.contains("Glyph '1' has no tetrominos"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn glyphs_which_are_not_animated() {
+        for table in ["glyphs", "bitmaps"] {
+            let value = if table == "glyphs" { "[]" } else { "\"#\"" };
+            let message = error(&format!("[{}]\n\":\" = {}", table, value));
+            assert!(
+                message.contains("The font can not contain the glyph ':'"),
+                "{}",
+                message
+            );
+        }
+    }
+
+    #[test]
+    fn keys_of_several_characters() {
+        for key in ["12", ""] {
+            let message = error(&format!("[glyphs]\n\"{}\" = []", key));
+            assert!(
+                message.contains("Glyphs must be a single character"),
+                "{}",
+                message
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_bitmap() {
+        let message = error("[bitmaps]\n\"1\" = \"#o\"");
+        assert!(
+            message.contains("Invalid bitmap of glyph '1': Invalid cell 'o'"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn unknown_fields() {
+        for toml in [
+            "[glyphs]\n\"1\" = [{ shape = \"O\", dx = 4, dy = 1 }]",
+            "[glyph]\n\"1\" = []",
+        ] {
+            let message = error(toml);
+            assert!(message.contains("unknown field"), "{}", message);
+        }
+    }
+}
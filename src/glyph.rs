@@ -0,0 +1,560 @@
+use crate::digits::{Animation, Digit, FallingTetromino};
+use crate::tetromino::{Rotation, Shape};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Letter {
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+}
+
+impl Letter {
+    const ALL: [Letter; 26] = [
+        Letter::A,
+        Letter::B,
+        Letter::C,
+        Letter::D,
+        Letter::E,
+        Letter::F,
+        Letter::G,
+        Letter::H,
+        Letter::I,
+        Letter::J,
+        Letter::K,
+        Letter::L,
+        Letter::M,
+        Letter::N,
+        Letter::O,
+        Letter::P,
+        Letter::Q,
+        Letter::R,
+        Letter::S,
+        Letter::T,
+        Letter::U,
+        Letter::V,
+        Letter::W,
+        Letter::X,
+        Letter::Y,
+        Letter::Z,
+    ];
+
+    /// The letter of an ASCII character, regardless of its case
+    pub fn from_char(c: char) -> Option<Self> {
+        let c = c.to_ascii_uppercase();
+        c.is_ascii_uppercase()
+            .then(|| Self::ALL[(c as u8 - b'A') as usize])
+    }
+}
+
+impl From<Letter> for Animation {
+    fn from(letter: Letter) -> Self {
+        use Rotation::*;
+        use Shape::*;
+        match letter {
+            Letter::A => vec![
+                FallingTetromino::new(O, NoRotation, 0),
+                FallingTetromino::new(J, Degrees270, 1),
+                FallingTetromino::new(O, NoRotation, 4),
+                FallingTetromino::new(O, NoRotation, 4),
+                FallingTetromino::new(Z, NoRotation, 3),
+                FallingTetromino::new(S, Degrees90, 5),
+                FallingTetromino::new(S, Degrees90, 5),
+                FallingTetromino::new(I, Degrees90, 0),
+                FallingTetromino::new(S, Degrees90, 1),
+                FallingTetromino::new(L, NoRotation, 1),
+                FallingTetromino::new(L, NoRotation, 0),
+                FallingTetromino::new(J, NoRotation, 5),
+            ],
+            Letter::B => vec![
+                FallingTetromino::new(L, Degrees180, 0),
+                FallingTetromino::new(L, Degrees270, 0),
+                FallingTetromino::new(L, Degrees90, 1),
+                FallingTetromino::new(L, Degrees270, 0),
+                FallingTetromino::new(L, Degrees90, 1),
+                FallingTetromino::new(O, NoRotation, 3),
+                FallingTetromino::new(T, Degrees90, 5),
+                FallingTetromino::new(J, NoRotation, 4),
+                FallingTetromino::new(L, Degrees180, 2),
+                FallingTetromino::new(J, NoRotation, 2),
+                FallingTetromino::new(T, Degrees90, 5),
+                FallingTetromino::new(O, NoRotation, 3),
+            ],
+            Letter::C => vec![
+                FallingTetromino::new(L, Degrees180, 1),
+                FallingTetromino::new(L, NoRotation, 0),
+                FallingTetromino::new(L, Degrees270, 0),
+                FallingTetromino::new(L, Degrees90, 1),
+                FallingTetromino::new(O, NoRotation, 0),
+                FallingTetromino::new(O, NoRotation, 4),
+                FallingTetromino::new(I, NoRotation, 1),
+                FallingTetromino::new(L, NoRotation, 0),
+                FallingTetromino::new(J, NoRotation, 5),
+            ],
+            Letter::D => vec![
+                FallingTetromino::new(L, Degrees180, 0),
+                FallingTetromino::new(L, Degrees270, 0),
+                FallingTetromino::new(L, Degrees90, 1),
+                FallingTetromino::new(L, Degrees270, 0),
+                FallingTetromino::new(L, Degrees90, 1),
+                FallingTetromino::new(L, Degrees270, 3),
+                FallingTetromino::new(L, Degrees270, 4),
+                FallingTetromino::new(L, Degrees90, 5),
+                FallingTetromino::new(L, Degrees90, 4),
+                FallingTetromino::new(L, Degrees180, 2),
+                FallingTetromino::new(I, NoRotation, 0),
+                FallingTetromino::new(I, Degrees90, 5),
+            ],
+            Letter::E => vec![
+                FallingTetromino::new(L, Degrees180, 1),
+                FallingTetromino::new(L, NoRotation, 0),
+                FallingTetromino::new(O, NoRotation, 0),
+                FallingTetromino::new(O, NoRotation, 4),
+                FallingTetromino::new(I, NoRotation, 1),
+                FallingTetromino::new(L, NoRotation, 0),
+                FallingTetromino::new(J, NoRotation, 5),
+                FallingTetromino::new(O, NoRotation, 0),
+                FallingTetromino::new(I, NoRotation, 1),
+                FallingTetromino::new(L, NoRotation, 0),
+                FallingTetromino::new(J, NoRotation, 5),
+            ],
+            Letter::F => vec![
+                FallingTetromino::new(L, Degrees270, 0),
+                FallingTetromino::new(L, Degrees90, 1),
+                FallingTetromino::new(I, NoRotation, 1),
+                FallingTetromino::new(L, NoRotation, 0),
+                FallingTetromino::new(J, NoRotation, 5),
+                FallingTetromino::new(O, NoRotation, 0),
+                FallingTetromino::new(I, NoRotation, 1),
+                FallingTetromino::new(L, NoRotation, 0),
+                FallingTetromino::new(J, NoRotation, 5),
+            ],
+            Letter::G => vec![
+                FallingTetromino::new(L, Degrees180, 1),
+                FallingTetromino::new(L, NoRotation, 0),
+                FallingTetromino::new(L, Degrees270, 0),
+                FallingTetromino::new(L, Degrees90, 1),
+                FallingTetromino::new(L, Degrees270, 4),
+                FallingTetromino::new(L, Degrees90, 5),
+                FallingTetromino::new(O, NoRotation, 0),
+                FallingTetromino::new(O, NoRotation, 4),
+                FallingTetromino::new(I, NoRotation, 1),
+                FallingTetromino::new(L, NoRotation, 0),
+                FallingTetromino::new(J, NoRotation, 5),
+            ],
+            Letter::H => vec![
+                FallingTetromino::new(L, Degrees270, 0),
+                FallingTetromino::new(L, Degrees90, 1),
+                FallingTetromino::new(L, Degrees180, 1),
+                FallingTetromino::new(L, NoRotation, 0),
+                FallingTetromino::new(L, Degrees270, 0),
+                FallingTetromino::new(L, Degrees90, 1),
+                FallingTetromino::new(L, Degrees270, 4),
+                FallingTetromino::new(L, Degrees90, 5),
+                FallingTetromino::new(L, Degrees270, 4),
+                FallingTetromino::new(L, Degrees90, 5),
+                FallingTetromino::new(O, NoRotation, 4),
+            ],
+            Letter::I => vec![
+                FallingTetromino::new(L, Degrees180, 1),
+                FallingTetromino::new(L, NoRotation, 0),
+                FallingTetromino::new(L, Degrees270, 2),
+                FallingTetromino::new(L, Degrees90, 3),
+                FallingTetromino::new(L, Degrees90, 2),
+                FallingTetromino::new(L, NoRotation, 0),
+                FallingTetromino::new(O, NoRotation, 4),
+                FallingTetromino::new(J, Degrees270, 3),
+                FallingTetromino::new(J, NoRotation, 5),
+            ],
+            Letter::J => vec![
+                FallingTetromino::new(L, Degrees180, 1),
+                FallingTetromino::new(L, NoRotation, 0),
+                FallingTetromino::new(L, Degrees270, 4),
+                FallingTetromino::new(L, Degrees90, 5),
+                FallingTetromino::new(L, Degrees270, 4),
+                FallingTetromino::new(L, Degrees90, 5),
+                FallingTetromino::new(O, NoRotation, 0),
+                FallingTetromino::new(O, NoRotation, 4),
+            ],
+            Letter::K => vec![
+                FallingTetromino::new(L, Degrees270, 0),
+                FallingTetromino::new(L, Degrees90, 1),
+                FallingTetromino::new(L, Degrees270, 0),
+                FallingTetromino::new(L, Degrees90, 1),
+                FallingTetromino::new(J, Degrees90, 4),
+                FallingTetromino::new(O, NoRotation, 0),
+                FallingTetromino::new(T, Degrees90, 4),
+                FallingTetromino::new(L, Degrees270, 2),
+                FallingTetromino::new(L, Degrees90, 3),
+                FallingTetromino::new(T, NoRotation, 4),
+                FallingTetromino::new(O, NoRotation, 4),
+            ],
+            Letter::L => vec![
+                FallingTetromino::new(L, Degrees180, 1),
+                FallingTetromino::new(L, NoRotation, 0),
+                FallingTetromino::new(L, Degrees270, 0),
+                FallingTetromino::new(L, Degrees90, 1),
+                FallingTetromino::new(L, Degrees270, 0),
+                FallingTetromino::new(L, Degrees90, 1),
+                FallingTetromino::new(O, NoRotation, 4),
+            ],
+            Letter::M => vec![
+                FallingTetromino::new(I, Degrees90, 0),
+                FallingTetromino::new(I, Degrees90, 1),
+                FallingTetromino::new(I, Degrees90, 4),
+                FallingTetromino::new(I, Degrees90, 5),
+                FallingTetromino::new(L, Degrees270, 0),
+                FallingTetromino::new(J, Degrees270, 1),
+                FallingTetromino::new(T, Degrees270, 0),
+                FallingTetromino::new(O, NoRotation, 4),
+                FallingTetromino::new(Z, NoRotation, 4),
+                FallingTetromino::new(T, Degrees90, 5),
+            ],
+            Letter::N => vec![
+                FallingTetromino::new(L, Degrees270, 0),
+                FallingTetromino::new(L, Degrees90, 1),
+                FallingTetromino::new(L, Degrees270, 0),
+                FallingTetromino::new(L, Degrees90, 1),
+                FallingTetromino::new(L, Degrees270, 4),
+                FallingTetromino::new(L, Degrees90, 5),
+                FallingTetromino::new(L, Degrees90, 4),
+                FallingTetromino::new(J, Degrees180, 2),
+                FallingTetromino::new(O, NoRotation, 0),
+                FallingTetromino::new(I, Degrees90, 5),
+                FallingTetromino::new(O, NoRotation, 4),
+            ],
+            Letter::O => vec![
+                FallingTetromino::new(L, Degrees180, 0),
+                FallingTetromino::new(L, Degrees180, 3),
+                FallingTetromino::new(L, Degrees270, 0),
+                FallingTetromino::new(L, Degrees90, 1),
+                FallingTetromino::new(L, Degrees270, 0),
+                FallingTetromino::new(L, Degrees90, 1),
+                FallingTetromino::new(J, NoRotation, 2),
+                FallingTetromino::new(J, Degrees90, 3),
+                FallingTetromino::new(L, Degrees90, 5),
+                FallingTetromino::new(L, Degrees270, 4),
+                FallingTetromino::new(L, Degrees90, 5),
+                FallingTetromino::new(L, NoRotation, 3),
+            ],
+            Letter::P => vec![
+                FallingTetromino::new(O, NoRotation, 0),
+                FallingTetromino::new(J, Degrees270, 1),
+                FallingTetromino::new(Z, NoRotation, 3),
+                FallingTetromino::new(S, Degrees90, 5),
+                FallingTetromino::new(S, Degrees90, 5),
+                FallingTetromino::new(I, Degrees90, 0),
+                FallingTetromino::new(S, Degrees90, 1),
+                FallingTetromino::new(L, NoRotation, 1),
+                FallingTetromino::new(L, NoRotation, 0),
+                FallingTetromino::new(J, NoRotation, 5),
+            ],
+            Letter::Q => vec![
+                FallingTetromino::new(L, Degrees90, 4),
+                FallingTetromino::new(I, Degrees90, 4),
+                FallingTetromino::new(I, Degrees90, 5),
+                FallingTetromino::new(I, Degrees90, 5),
+                FallingTetromino::new(S, NoRotation, 1),
+                FallingTetromino::new(J, NoRotation, 4),
+                FallingTetromino::new(J, NoRotation, 5),
+                FallingTetromino::new(T, Degrees270, 0),
+                FallingTetromino::new(T, Degrees90, 1),
+                FallingTetromino::new(J, Degrees270, 1),
+                FallingTetromino::new(I, Degrees90, 0),
+            ],
+            Letter::R => vec![
+                FallingTetromino::new(L, Degrees270, 0),
+                FallingTetromino::new(L, NoRotation, 1),
+                FallingTetromino::new(L, Degrees180, 0),
+                FallingTetromino::new(L, Degrees270, 0),
+                FallingTetromino::new(L, Degrees90, 1),
+                FallingTetromino::new(O, NoRotation, 0),
+                FallingTetromino::new(O, NoRotation, 4),
+                FallingTetromino::new(L, NoRotation, 3),
+                FallingTetromino::new(L, Degrees180, 2),
+                FallingTetromino::new(L, Degrees90, 5),
+                FallingTetromino::new(L, Degrees180, 3),
+                FallingTetromino::new(L, NoRotation, 2),
+            ],
+            Letter::S => vec![
+                FallingTetromino::new(L, Degrees180, 1),
+                FallingTetromino::new(L, NoRotation, 0),
+                FallingTetromino::new(L, Degrees270, 4),
+                FallingTetromino::new(L, Degrees90, 5),
+                FallingTetromino::new(I, NoRotation, 1),
+                FallingTetromino::new(L, NoRotation, 0),
+                FallingTetromino::new(J, NoRotation, 5),
+                FallingTetromino::new(O, NoRotation, 0),
+                FallingTetromino::new(I, NoRotation, 1),
+                FallingTetromino::new(L, NoRotation, 0),
+                FallingTetromino::new(J, NoRotation, 5),
+            ],
+            Letter::T => vec![
+                FallingTetromino::new(L, Degrees270, 2),
+                FallingTetromino::new(L, Degrees90, 3),
+                FallingTetromino::new(L, Degrees270, 2),
+                FallingTetromino::new(L, Degrees90, 3),
+                FallingTetromino::new(L, Degrees180, 3),
+                FallingTetromino::new(J, Degrees180, 0),
+                FallingTetromino::new(I, NoRotation, 1),
+            ],
+            Letter::U => vec![
+                FallingTetromino::new(L, Degrees180, 1),
+                FallingTetromino::new(L, NoRotation, 0),
+                FallingTetromino::new(L, Degrees270, 0),
+                FallingTetromino::new(L, Degrees90, 1),
+                FallingTetromino::new(L, Degrees270, 0),
+                FallingTetromino::new(L, Degrees90, 1),
+                FallingTetromino::new(L, Degrees270, 4),
+                FallingTetromino::new(L, Degrees90, 5),
+                FallingTetromino::new(L, Degrees270, 4),
+                FallingTetromino::new(L, Degrees90, 5),
+                FallingTetromino::new(O, NoRotation, 4),
+            ],
+            Letter::V => vec![
+                FallingTetromino::new(L, Degrees90, 2),
+                FallingTetromino::new(J, Degrees270, 3),
+                FallingTetromino::new(I, NoRotation, 1),
+                FallingTetromino::new(L, Degrees270, 0),
+                FallingTetromino::new(L, Degrees90, 1),
+                FallingTetromino::new(L, Degrees270, 4),
+                FallingTetromino::new(L, Degrees90, 5),
+                FallingTetromino::new(O, NoRotation, 0),
+                FallingTetromino::new(O, NoRotation, 4),
+            ],
+            Letter::W => vec![
+                FallingTetromino::new(T, Degrees90, 5),
+                FallingTetromino::new(J, Degrees90, 3),
+                FallingTetromino::new(L, Degrees90, 5),
+                FallingTetromino::new(L, Degrees270, 4),
+                FallingTetromino::new(L, Degrees90, 5),
+                FallingTetromino::new(T, Degrees270, 0),
+                FallingTetromino::new(L, Degrees270, 1),
+                FallingTetromino::new(J, Degrees270, 0),
+                FallingTetromino::new(L, Degrees270, 0),
+                FallingTetromino::new(L, Degrees90, 1),
+            ],
+            Letter::X => vec![
+                FallingTetromino::new(O, NoRotation, 0),
+                FallingTetromino::new(O, NoRotation, 4),
+                FallingTetromino::new(S, Degrees90, 4),
+                FallingTetromino::new(Z, Degrees90, 1),
+                FallingTetromino::new(S, Degrees90, 2),
+                FallingTetromino::new(O, NoRotation, 0),
+                FallingTetromino::new(Z, Degrees90, 3),
+                FallingTetromino::new(O, NoRotation, 4),
+            ],
+            Letter::Y => vec![
+                FallingTetromino::new(L, Degrees270, 2),
+                FallingTetromino::new(L, Degrees90, 3),
+                FallingTetromino::new(O, NoRotation, 2),
+                FallingTetromino::new(L, Degrees270, 1),
+                FallingTetromino::new(J, Degrees90, 3),
+                FallingTetromino::new(L, Degrees90, 5),
+                FallingTetromino::new(J, Degrees270, 0),
+            ],
+            Letter::Z => vec![
+                FallingTetromino::new(L, Degrees180, 1),
+                FallingTetromino::new(L, NoRotation, 0),
+                FallingTetromino::new(O, NoRotation, 0),
+                FallingTetromino::new(O, NoRotation, 4),
+                FallingTetromino::new(T, NoRotation, 2),
+                FallingTetromino::new(T, Degrees180, 2),
+                FallingTetromino::new(O, NoRotation, 4),
+                FallingTetromino::new(I, NoRotation, 1),
+                FallingTetromino::new(L, NoRotation, 0),
+                FallingTetromino::new(J, NoRotation, 5),
+            ],
+        }
+    }
+}
+
+/// A single tetromino of a separator glyph. Separators are not stacked on a
+/// `DigitBoard`, instead every piece stops `lift` cells above the bottom of
+/// the glyph and stays in the air on its own.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FloatingTetromino {
+    pub shape: Shape,
+    pub rotation: Rotation,
+    pub dx: i64,
+    pub lift: i64,
+}
+
+impl FloatingTetromino {
+    pub fn new(shape: Shape, rotation: Rotation, dx: i64, lift: i64) -> Self {
+        Self {
+            shape,
+            rotation,
+            dx,
+            lift,
+        }
+    }
+}
+
+/// Everything which can be displayed by a [`Clock`](crate::Clock).
+///
+/// Digits and letters are built from falling tetrominos on a `DigitBoard`.
+/// Punctuation marks, dashes, slashes and plus signs are made of a few
+/// floating tetrominos and spaces are left empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Glyph {
+    Digit(Digit),
+    Letter(Letter),
+    Colon,
+    Dot,
+    Comma,
+    Apostrophe,
+    Exclamation,
+    Question,
+    Dash,
+    Slash,
+    Plus,
+    Space,
+}
+
+impl From<Digit> for Glyph {
+    fn from(digit: Digit) -> Self {
+        Self::Digit(digit)
+    }
+}
+
+impl From<Letter> for Glyph {
+    fn from(letter: Letter) -> Self {
+        Self::Letter(letter)
+    }
+}
+
+impl Glyph {
+    pub fn from_char(c: char) -> Option<Self> {
+        match c.to_ascii_uppercase() {
+            '0'..='9' => c.to_digit(10).map(|d| Self::Digit(Digit::from(d))),
+            'A'..='Z' => Letter::from_char(c).map(Self::Letter),
+            ':' => Some(Self::Colon),
+            '.' => Some(Self::Dot),
+            ',' => Some(Self::Comma),
+            '\'' => Some(Self::Apostrophe),
+            '!' => Some(Self::Exclamation),
+            '?' => Some(Self::Question),
+            '-' => Some(Self::Dash),
+            '/' => Some(Self::Slash),
+            '+' => Some(Self::Plus),
+            ' ' => Some(Self::Space),
+            _ => None,
+        }
+    }
+
+    /// Convert a string into glyphs. The first character without a glyph is
+    /// returned as error.
+    pub fn parse(s: &str) -> Result<Vec<Self>, char> {
+        s.chars().map(|c| Self::from_char(c).ok_or(c)).collect()
+    }
+
+    /// Width of the glyph in pixels
+    pub fn width(&self) -> i64 {
+        match self {
+            Self::Digit(_) | Self::Letter(_) | Self::Question => 6,
+            Self::Dash | Self::Slash | Self::Plus => 4,
+            Self::Colon
+            | Self::Dot
+            | Self::Comma
+            | Self::Apostrophe
+            | Self::Exclamation
+            | Self::Space => 2,
+        }
+    }
+
+    /// The floating tetrominos a separator glyph is made of. Animated glyphs
+    /// and spaces have none.
+    pub fn floating_tetrominos(&self) -> Vec<FloatingTetromino> {
+        use Rotation::*;
+        use Shape::*;
+        match self {
+            Self::Colon => vec![
+                FloatingTetromino::new(O, NoRotation, 0, 2),
+                FloatingTetromino::new(O, NoRotation, 0, 6),
+            ],
+            Self::Dot => vec![FloatingTetromino::new(O, NoRotation, 0, 0)],
+            Self::Comma => vec![FloatingTetromino::new(Z, Degrees90, 0, 0)],
+            Self::Apostrophe => vec![FloatingTetromino::new(Z, Degrees90, 0, 7)],
+            Self::Exclamation => vec![
+                FloatingTetromino::new(O, NoRotation, 0, 0),
+                FloatingTetromino::new(I, Degrees90, 0, 4),
+                FloatingTetromino::new(I, Degrees90, 1, 4),
+                FloatingTetromino::new(O, NoRotation, 0, 8),
+            ],
+            Self::Question => vec![
+                FloatingTetromino::new(O, NoRotation, 2, 0),
+                FloatingTetromino::new(O, NoRotation, 2, 4),
+                FloatingTetromino::new(O, NoRotation, 4, 4),
+                FloatingTetromino::new(O, NoRotation, 4, 6),
+                FloatingTetromino::new(O, NoRotation, 0, 8),
+                FloatingTetromino::new(O, NoRotation, 2, 8),
+                FloatingTetromino::new(O, NoRotation, 4, 8),
+            ],
+            Self::Dash => vec![
+                FloatingTetromino::new(O, NoRotation, 0, 4),
+                FloatingTetromino::new(O, NoRotation, 2, 4),
+            ],
+            // Upright Z pieces, each one continuing the diagonal of the one
+            // below it
+            Self::Slash => vec![
+                FloatingTetromino::new(Z, Degrees90, 0, 0),
+                FloatingTetromino::new(Z, Degrees90, 1, 3),
+                FloatingTetromino::new(Z, Degrees90, 2, 6),
+            ],
+            Self::Plus => vec![
+                FloatingTetromino::new(O, NoRotation, 1, 2),
+                FloatingTetromino::new(O, NoRotation, 0, 4),
+                FloatingTetromino::new(O, NoRotation, 2, 4),
+                FloatingTetromino::new(O, NoRotation, 1, 6),
+            ],
+            Self::Digit(_) | Self::Letter(_) | Self::Space => vec![],
+        }
+    }
+
+    /// Whether the glyph is built from falling tetrominos on a `DigitBoard`
+    pub fn is_animated(&self) -> bool {
+        matches!(self, Self::Digit(_) | Self::Letter(_))
+    }
+}
+
+impl From<Glyph> for Animation {
+    fn from(glyph: Glyph) -> Self {
+        match glyph {
+            Glyph::Digit(digit) => digit.into(),
+            Glyph::Letter(letter) => letter.into(),
+            Glyph::Colon
+            | Glyph::Dot
+            | Glyph::Comma
+            | Glyph::Apostrophe
+            | Glyph::Exclamation
+            | Glyph::Question
+            | Glyph::Dash
+            | Glyph::Slash
+            | Glyph::Plus
+            | Glyph::Space => vec![],
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use chrono::Duration;
+use pixel_loop::canvas::Canvas;
+use pixel_loop::color::Color;
+
+use crate::mode::Lap;
+
+// Laps are written using a tiny font instead of tetrominos, to keep the list
+// compact. Every character is given as rows of filled (#) cells.
+fn mini_glyph(c: char) -> [&'static str; 5] {
+    match c {
+        '0' => ["###", "#.#", "#.#", "#.#", "###"],
+        '1' => [".#.", "##.", ".#.", ".#.", "###"],
+        '2' => ["###", "..#", "###", "#..", "###"],
+        '3' => ["###", "..#", ".##", "..#", "###"],
+        '4' => ["#.#", "#.#", "###", "..#", "..#"],
+        '5' => ["###", "#..", "###", "..#", "###"],
+        '6' => ["###", "#..", "###", "#.#", "###"],
+        '7' => ["###", "..#", "..#", "..#", "..#"],
+        '8' => ["###", "#.#", "###", "#.#", "###"],
+        '9' => ["###", "#.#", "###", "..#", "###"],
+        ':' => [".", "#", ".", "#", "."],
+        _ => [".", ".", ".", ".", "."],
+    }
+}
+
+const MINI_HEIGHT: i64 = 5;
+
+fn mini_width(text: &str) -> i64 {
+    let gaps = (text.chars().count() as i64 - 1).max(0);
+    text.chars()
+        .map(|c| mini_glyph(c)[0].len() as i64)
+        .sum::<i64>()
+        + gaps
+}
+
+// Lap and split times are shown as MM:SS, hours are only added once needed
+fn format_lap_duration(duration: Duration) -> String {
+    let hours = duration.num_hours();
+    let minutes = duration.num_minutes() % 60;
+    let seconds = duration.num_seconds() % 60;
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{:02}:{:02}", minutes, seconds)
+    }
+}
+
+/// Shows the most recent laps of a stopwatch, one per row with the lap
+/// number, the lap time and the split time. The newest lap is on top.
+pub struct LapList {
+    x_center: i64,
+    y_start: i64,
+    scale: i64,
+    color: Color,
+}
+
+impl LapList {
+    /// Number of laps shown at once
+    pub const ROWS: usize = 3;
+    /// Height of the list in cells
+    pub const HEIGHT: i64 = Self::ROWS as i64 * (MINI_HEIGHT + 1) - 1;
+
+    /// Create a list horizontally centered on `x_center` with its top at
+    /// `y_start` (both in pixels)
+    pub fn new(x_center: i64, y_start: i64, color: Color) -> Self {
+        Self {
+            x_center,
+            y_start,
+            scale: 1,
+            color,
+        }
+    }
+
+    pub fn with_scale(mut self, scale: i64) -> Self {
+        self.scale = scale.max(1);
+        self
+    }
+
+    pub fn render<C: Canvas>(&self, canvas: &mut C, laps: &[Lap]) {
+        let scale = self.scale;
+        let recent = laps.iter().rev().take(Self::ROWS);
+        for (row, lap) in recent.enumerate() {
+            let text = format!(
+                "{:02} {} {}",
+                lap.number,
+                format_lap_duration(lap.time),
+                format_lap_duration(lap.split)
+            );
+            let mut x = self.x_center - mini_width(&text) * scale / 2;
+            let y = self.y_start + row as i64 * (MINI_HEIGHT + 1) * scale;
+            for c in text.chars() {
+                let glyph = mini_glyph(c);
+                for (dy, line) in glyph.iter().enumerate() {
+                    for (dx, _) in line.chars().enumerate().filter(|(_, c)| *c == '#') {
+                        canvas.filled_rect(
+                            x + dx as i64 * scale,
+                            y + dy as i64 * scale,
+                            scale as u32,
+                            scale as u32,
+                            &self.color,
+                        );
+                    }
+                }
+                x += (glyph[0].len() as i64 + 1) * scale;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pixel_loop::canvas::InMemoryCanvas;
+
+    use super::*;
+
+    fn laps(count: i64) -> Vec<Lap> {
+        (1..=count)
+            .map(|n| Lap {
+                number: n as usize,
+                time: Duration::seconds(n),
+                split: Duration::seconds(n * (n + 1) / 2),
+            })
+            .collect()
+    }
+
+    fn render(laps: &[Lap]) -> InMemoryCanvas {
+        let mut canvas = InMemoryCanvas::new(60, 30, &Color::from_rgb(0, 0, 0));
+        LapList::new(30, 2, Color::from_rgb(255, 255, 255)).render(&mut canvas, laps);
+        canvas
+    }
+
+    fn rows(canvas: &InMemoryCanvas, rows: std::ops::Range<u32>) -> Vec<Color> {
+        rows.flat_map(|y| (0..canvas.width()).map(move |x| *canvas.get(x, y)))
+            .collect()
+    }
+
+    #[test]
+    fn lap_durations_only_show_hours_once_needed() {
+        assert_eq!(format_lap_duration(Duration::seconds(65)), "01:05");
+        assert_eq!(format_lap_duration(Duration::seconds(3723)), "1:02:03");
+    }
+
+    #[test]
+    fn only_the_latest_laps_are_listed_newest_first() {
+        let all = render(&laps(5));
+        assert_eq!(rows(&all, 0..30), rows(&render(&laps(5)[2..]), 0..30));
+
+        // Every row holds a single lap, starting with the newest one
+        let row_height = (MINI_HEIGHT + 1) as u32;
+        for (row, lap) in [4, 3, 2].into_iter().enumerate() {
+            let y = 2 + row as u32 * row_height;
+            let single = render(&laps(5)[lap..=lap]);
+            assert_eq!(
+                rows(&all, y..y + row_height),
+                rows(&single, 2..2 + row_height),
+                "{}",
+                row
+            );
+        }
+        let below = 2 + LapList::HEIGHT as u32;
+        assert!(rows(&all, below..30)
+            .iter()
+            .all(|&color| color == Color::from_rgb(0, 0, 0)));
+    }
+}
@@ -0,0 +1,159 @@
+use crate::glyph::Glyph;
+
+/// Default horizontal space between two glyphs
+pub const DEFAULT_SPACING: i64 = 2;
+/// Default vertical space between two rows of glyphs
+pub const DEFAULT_ROW_SPACING: i64 = 4;
+/// Height of every glyph
+pub const GLYPH_HEIGHT: i64 = 10;
+
+/// A glyph together with the coordinates of its upper left corner
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    pub glyph: Glyph,
+    pub x: i64,
+    pub y: i64,
+}
+
+/// Computes the positions of a sequence of glyphs.
+///
+/// Glyphs are placed from left to right using their individual
+/// [`Glyph::width`], separated by a fixed spacing. Multiple rows of glyphs
+/// are stacked from top to bottom, each row centered below the widest one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+    spacing: i64,
+    row_spacing: i64,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self::new(DEFAULT_SPACING)
+    }
+}
+
+impl Layout {
+    pub fn new(spacing: i64) -> Self {
+        Self {
+            spacing,
+            row_spacing: DEFAULT_ROW_SPACING,
+        }
+    }
+
+    pub fn with_row_spacing(mut self, row_spacing: i64) -> Self {
+        self.row_spacing = row_spacing;
+        self
+    }
+
+    pub fn spacing(&self) -> i64 {
+        self.spacing
+    }
+
+    pub fn row_spacing(&self) -> i64 {
+        self.row_spacing
+    }
+
+    /// Total width of all glyphs including the spacing between them
+    pub fn width(&self, glyphs: &[Glyph]) -> i64 {
+        let spacing = self.spacing * (glyphs.len() as i64 - 1).max(0);
+        glyphs.iter().map(|glyph| glyph.width()).sum::<i64>() + spacing
+    }
+
+    /// Width of the widest row
+    pub fn rows_width(&self, rows: &[Vec<Glyph>]) -> i64 {
+        rows.iter().map(|row| self.width(row)).max().unwrap_or(0)
+    }
+
+    /// Total height of the given number of rows including the spacing
+    /// between them
+    pub fn rows_height(&self, rows: usize) -> i64 {
+        let spacing = self.row_spacing * (rows as i64 - 1).max(0);
+        GLYPH_HEIGHT * rows as i64 + spacing
+    }
+
+    /// Place all glyphs starting at `x_start` in a single row at the top
+    pub fn place(&self, glyphs: &[Glyph], x_start: i64) -> Vec<Placement> {
+        let mut x = x_start;
+        glyphs
+            .iter()
+            .map(|&glyph| {
+                let placement = Placement { glyph, x, y: 0 };
+                x += glyph.width() + self.spacing;
+                placement
+            })
+            .collect()
+    }
+
+    /// Place multiple rows of glyphs with the upper left corner of the block
+    /// at `(x_start, y_start)`. Shorter rows are centered below the widest
+    /// one.
+    pub fn place_rows(&self, rows: &[Vec<Glyph>], x_start: i64, y_start: i64) -> Vec<Placement> {
+        let width = self.rows_width(rows);
+        let mut y = y_start;
+        let mut placements = vec![];
+        for row in rows {
+            let x = x_start + (width - self.width(row)) / 2;
+            placements.extend(
+                self.place(row, x)
+                    .into_iter()
+                    .map(|placement| Placement { y, ..placement }),
+            );
+            y += GLYPH_HEIGHT + self.row_spacing;
+        }
+        placements
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glyphs(s: &str) -> Vec<Glyph> {
+        Glyph::parse(s).unwrap()
+    }
+
+    #[test]
+    fn width_includes_spacing() {
+        let layout = Layout::new(2);
+        assert_eq!(layout.width(&[]), 0);
+        assert_eq!(layout.width(&glyphs("1")), 6);
+        assert_eq!(
+            layout.width(&glyphs("12:34")),
+            4 * 6 + Glyph::Colon.width() + 4 * 2
+        );
+        assert_eq!(Layout::new(0).width(&glyphs("12")), 12);
+    }
+
+    #[test]
+    fn rows_height_includes_row_spacing() {
+        let layout = Layout::new(2).with_row_spacing(3);
+        assert_eq!(layout.rows_height(0), 0);
+        assert_eq!(layout.rows_height(1), GLYPH_HEIGHT);
+        assert_eq!(layout.rows_height(3), 3 * GLYPH_HEIGHT + 2 * 3);
+    }
+
+    #[test]
+    fn place_advances_by_width_and_spacing() {
+        let placements = Layout::new(3).place(&glyphs("1:2"), 5);
+        let xs: Vec<i64> = placements.iter().map(|placement| placement.x).collect();
+        assert_eq!(xs, [5, 14, 14 + Glyph::Colon.width() + 3]);
+        assert!(placements.iter().all(|placement| placement.y == 0));
+    }
+
+    #[test]
+    fn shorter_rows_are_centered() {
+        let layout = Layout::new(2).with_row_spacing(4);
+        let rows = [glyphs("1234"), glyphs("56")];
+        let placements = layout.place_rows(&rows, 10, 20);
+        assert_eq!(layout.rows_width(&rows), 30);
+        assert_eq!(
+            placements[4],
+            Placement {
+                glyph: glyphs("5")[0],
+                x: 10 + (30 - 14) / 2,
+                y: 20 + GLYPH_HEIGHT + 4,
+            }
+        );
+        assert_eq!((placements[0].x, placements[0].y), (10, 20));
+    }
+}
@@ -0,0 +1,40 @@
+//! Tetromino based clock, timer and stopwatch rendering for
+//! [PixelLoop](https://crates.io/crates/pixel_loop).
+//!
+//! The [`Clock`] widget is the high level entry point: create one with a
+//! [`Mode`] and a [`Colorscheme`], position it on a canvas and call
+//! [`Clock::update`] and [`Clock::render`] from within your own pixel loop.
+//! The lower level [`Board`] and [`DigitBoard`] types can be used to build
+//! custom tetromino displays.
+
+pub mod clock;
+pub mod digits;
+pub mod font;
+pub mod glyph;
+pub mod laps;
+pub mod layout;
+pub mod mode;
+pub mod simulator;
+pub mod solver;
+pub mod tetromino;
+pub mod time;
+
+pub use clock::{Clock, ClockEvent};
+pub use digits::{Animation, Digit, FallingTetromino};
+pub use font::Font;
+pub use glyph::{FloatingTetromino, Glyph, Letter};
+pub use laps::LapList;
+pub use layout::{Layout, Placement};
+pub use mode::{
+    Interval, Lap, Mode, Phase, Pomodoro, Precision, Segment, Stopwatch, Zone, ZoneLabel,
+};
+pub use simulator::Bitmap;
+pub use tetromino::{
+    AnimationPolicy, Board, Colorscheme, DigitBoard, Physics, Rotation, Shape, Transition,
+};
+
+// Compile the examples of the README along with the doc tests, so they can't
+// go stale
+#[cfg(doctest)]
+#[doc = include_str!("../README.md")]
+struct ReadmeDoctests;
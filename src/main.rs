@@ -1,313 +1,1077 @@
-mod tetromino;
-mod digits;
+use std::fmt::Write as _;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::Duration as StdDuration;
 
-
-use std::vec;
-use anyhow::Result;
-use chrono::{DateTime, Duration, Local, NaiveTime, Timelike};
-use clap::{arg, Parser};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use chrono_tz::Tz;
+use clap::{Parser, Subcommand};
 use pixel_loop::canvas::CrosstermCanvas;
 use pixel_loop::canvas::{Canvas, RenderableCanvas};
 use pixel_loop::color::Color;
 use pixel_loop::input::{CrosstermInputState, KeyboardKey, KeyboardState};
 use pixel_loop::NextLoopState;
-use tetromino::{Board, Colorscheme, DigitBoard, Rotation, Shape};
-use digits::Digit;
+use tetrotime::layout::GLYPH_HEIGHT;
+use tetrotime::mode::{
+    FORMAT_12H, FORMAT_12H_SHORT, FORMAT_24H, FORMAT_24H_SHORT, FORMAT_DATE_DMY, FORMAT_DATE_MDY,
+    FORMAT_DATE_YMD,
+};
+use tetrotime::{
+    simulator, solver, AnimationPolicy, Bitmap, Clock, ClockEvent, Colorscheme, Font, Glyph,
+    Interval, Lap, Mode, Phase, Physics, Pomodoro, Precision, Segment, Stopwatch, Transition, Zone,
+    ZoneLabel,
+};
 
-fn time_to_digits(now: DateTime<Local>) -> Vec<Digit> {
-    let now_string = now.format("%H%M%S").to_string();
-    now_string.chars().map(|c| {
-       Digit::from(c.to_digit(10).unwrap())
-    }).collect()    
+#[derive(Parser, Debug)]
+#[command(
+    author = "Tutorial From Jakob Westhoff <jakob@westhoffswelt.de>",
+    about = "Tutorial From TetroTime - Time meets Tetris!"
+)]
+struct Args {
+    #[arg(short = 'c', long, group = "mode", help = "Show a clock")]
+    clock: bool,
+    #[arg(short = 'D', long, group = "mode", help = "Show the current date")]
+    date: bool,
+    #[arg(short = 'w', long, group = "mode", help = "Show a stopwatch")]
+    stopwatch: bool,
+    #[arg(
+        short = 'p',
+        long,
+        group = "mode",
+        help = "Show a pomodoro timer alternating between work phases and breaks"
+    )]
+    pomodoro: bool,
+    #[arg(
+        short = 'i',
+        long,
+        group = "mode",
+        value_delimiter = ',',
+        help = "Show an interval timer counting down each segment in turn (eg. \"AM=40,PM=20\"). Durations are given in seconds, MM:SS or HH:MM:SS",
+        value_name = "[LABEL=]DURATION,..."
+    )]
+    interval: Option<Vec<String>>,
+    #[arg(
+        long,
+        group = "mode",
+        help = "Show an interval timer with the segments read from a file, one [LABEL=]DURATION per line",
+        value_name = "PATH"
+    )]
+    interval_file: Option<PathBuf>,
+    #[arg(
+        long,
+        default_value_t = 1,
+        help = "Number of times the interval segments are repeated",
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    rounds: u32,
+    #[arg(
+        short = 'd',
+        long,
+        group = "mode",
+        help = "Show a countdown (Duration in HHMMSS or HH:MM:SS)",
+        value_name = "DURATION"
+    )]
+    countdown: Option<String>,
+    #[arg(
+        short = 'u',
+        long,
+        group = "mode",
+        help = "Show a countdown to the given time (HH:MM[:SS]) or date (YYYY-MM-DD[THH:MM[:SS]])",
+        value_name = "TARGET"
+    )]
+    until: Option<String>,
+    #[arg(
+        short = 't',
+        long,
+        group = "mode",
+        help = "Show the given text (eg. \"LUNCH\"). Letters, digits, spaces and .,:'!?-/+ can be displayed",
+        value_name = "TEXT"
+    )]
+    text: Option<String>,
+    #[arg(
+        short = 'f',
+        long,
+        default_value = "24h",
+        help = "Format of the clock: 24h, 24h-short, 12h, 12h-short or a strftime pattern (eg. \"%H:%M\")",
+        value_name = "FORMAT"
+    )]
+    format: String,
+    #[arg(
+        long,
+        default_value = "ymd",
+        help = "Format of the date: ymd (YYYY-MM-DD), dmy (DD/MM/YYYY), mdy (MM/DD/YYYY), locale or a strftime pattern",
+        value_name = "FORMAT"
+    )]
+    date_format: String,
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "date",
+            "stopwatch",
+            "countdown",
+            "until",
+            "world",
+            "pomodoro",
+            "interval",
+            "interval_file",
+            "text",
+        ],
+        help = "Show the date below the clock"
+    )]
+    with_date: bool,
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "stopwatch",
+            "countdown",
+            "until",
+            "world",
+            "pomodoro",
+            "interval",
+            "interval_file",
+            "text",
+        ],
+        help = "Show the clock or date in the given time zone (eg. Europe/Berlin)",
+        value_name = "ZONE"
+    )]
+    tz: Option<String>,
+    #[arg(
+        long,
+        group = "mode",
+        value_delimiter = ',',
+        help = "Show a world clock with one row per time zone, labeled with its UTC offset or the given LABEL (eg. \"Europe/Berlin,AM=America/New_York\")",
+        value_name = "[LABEL=]ZONE,..."
+    )]
+    world: Option<Vec<String>>,
+    #[arg(
+        long,
+        default_value = "1",
+        help = "Size of a tetromino cell in pixels, or \"auto\" to fill the terminal",
+        value_name = "N|auto",
+        value_parser = parse_scale
+    )]
+    scale: Scale,
+    #[arg(
+        long,
+        default_value_t = 2,
+        help = "Horizontal space between the digits in tetromino cells",
+        value_name = "PIXELS"
+    )]
+    spacing: i64,
+    #[arg(short='s', long, value_enum, default_value_t = Colorscheme::default(), help = "Select a specific colorscheme")]
+    colorscheme: Colorscheme,
+    #[arg(
+        long,
+        default_value_t = 1,
+        help = "Refresh the digits only every N seconds (for slow terminals)",
+        value_name = "SECONDS",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    tick: u32,
+    #[arg(
+        long,
+        default_value = "fall",
+        help = "How the tetrominos are brought into place: dropped one after the other, placed instantly or dropped FACTOR times faster (default 4)",
+        value_name = "fall|instant|fast[:FACTOR]",
+        value_parser = parse_animation
+    )]
+    animation: AnimationPolicy,
+    #[arg(
+        long,
+        default_value_t = 60,
+        help = "Number of frames (updates) per second (at most 240)",
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..=240)
+    )]
+    fps: u32,
+    #[arg(
+        long,
+        default_value_t = 60.0,
+        help = "Cells a tetromino falls per second (at most 10000)",
+        value_name = "CELLS",
+        value_parser = parse_fall_speed
+    )]
+    fall_speed: f64,
+    #[arg(
+        long,
+        default_value_t = 60,
+        help = "Milliseconds between dropping two tetrominos of a digit",
+        value_name = "MS",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    spawn_interval: u64,
+    #[arg(
+        long,
+        default_value_t = 0.0,
+        help = "Cells per second the speed of a falling tetromino increases every second (at most 10000)",
+        value_name = "CELLS",
+        value_parser = parse_gravity
+    )]
+    gravity: f64,
+    #[arg(
+        long,
+        help = "Only exchange the tetrominos which differ when a digit changes, instead of rebuilding the whole digit"
+    )]
+    keep_shared: bool,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = Transition::default(),
+        help = "How the previous digit leaves when a digit changes"
+    )]
+    transition: Transition,
+    #[arg(
+        long,
+        help = "Build the digits and letters from the tetrominos listed in the given TOML font file",
+        value_name = "PATH"
+    )]
+    font: Option<PathBuf>,
+    #[arg(
+        long,
+        default_value_t = 25,
+        help = "Length of a pomodoro work phase",
+        value_name = "MINUTES",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    work: u32,
+    #[arg(
+        long,
+        default_value_t = 5,
+        help = "Length of a short pomodoro break",
+        value_name = "MINUTES",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    short_break: u32,
+    #[arg(
+        long,
+        default_value_t = 15,
+        help = "Length of a long pomodoro break",
+        value_name = "MINUTES",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    long_break: u32,
+    #[arg(
+        long,
+        default_value_t = 4,
+        help = "Number of pomodoro work phases before a long break",
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    cycles: u32,
+    #[arg(long, value_enum, help = "Colorscheme during pomodoro work phases")]
+    work_colorscheme: Option<Colorscheme>,
+    #[arg(long, value_enum, help = "Colorscheme during short pomodoro breaks")]
+    short_break_colorscheme: Option<Colorscheme>,
+    #[arg(long, value_enum, help = "Colorscheme during long pomodoro breaks")]
+    long_break_colorscheme: Option<Colorscheme>,
+    #[arg(
+        long,
+        help = "Shell command to run once the countdown, a pomodoro phase or an interval segment finished",
+        value_name = "COMMAND"
+    )]
+    on_finish: Option<String>,
+    #[arg(
+        long,
+        help = "Exit with the given status code once the countdown or the last round of an interval finished",
+        value_name = "CODE"
+    )]
+    exit_on_finish: Option<i32>,
+    #[arg(
+        long,
+        help = "Do not ring the terminal bell once the countdown, a pomodoro phase or an interval segment finished"
+    )]
+    no_bell: bool,
+    #[arg(
+        long,
+        help = "Write the stopwatch laps as CSV to the given file on exit, instead of printing them",
+        value_name = "PATH"
+    )]
+    laps_file: Option<PathBuf>,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = Precision::default(),
+        requires = "stopwatch",
+        help = "Fractions of a second shown by the stopwatch"
+    )]
+    precision: Precision,
+    #[command(subcommand)]
+    action: Option<Action>,
 }
 
-fn time_string_to_digits<T: AsRef<str>>(time_string: T) -> Vec<Digit> {
-    time_string
-        .as_ref()
-        .chars()
-        .map(|c| Digit::from(c.to_digit(10).unwrap()))
-        .collect()
+#[derive(Subcommand, Debug)]
+enum Action {
+    /// Drop every glyph of a font file on an in-memory board and compare the
+    /// settled tetrominos against the bitmaps of the font
+    GlyphCheck {
+        #[arg(value_name = "FONT", help = "TOML font file to check")]
+        font: PathBuf,
+    },
+    /// Search for the tetrominos building the ASCII art of a glyph and print
+    /// them as font file entry
+    Solve {
+        #[arg(value_name = "GLYPH", help = "Digit or letter the bitmap shows")]
+        glyph: char,
+        #[arg(
+            value_name = "BITMAP",
+            help = "File with the ASCII art of the glyph, using '#' for covered and '.' for empty cells"
+        )]
+        bitmap: PathBuf,
+        #[arg(long, help = "Print the tetrominos as Rust code instead")]
+        rust: bool,
+    },
 }
 
-#[derive(Debug, Clone)]
-enum Mode {
-    Clock,
-    Countdown(DateTime<Local>),
-    Stopwatch(DateTime<Local>),
+#[derive(Debug, Clone, Copy)]
+enum Scale {
+    Fixed(i64),
+    Auto,
 }
 
-impl Default for Mode {
-    fn default() -> Self {
-        Self::Clock
+fn parse_scale(scale: &str) -> Result<Scale> {
+    if scale == "auto" {
+        return Ok(Scale::Auto);
+    }
+    match scale.parse::<i64>() {
+        Ok(scale) if scale >= 1 => Ok(Scale::Fixed(scale)),
+        _ => bail!("expected a positive number or \"auto\""),
     }
 }
 
-impl Mode {
-    fn get_timestring(&self) -> String {
-        match self {
-            Self::Clock => Local::now().format("%H%M%S").to_string(),
-            Self::Countdown(end) => {
-                let duration = end.signed_duration_since(Local::now());
-                // negative duration simply returns 000000
-                if duration.num_seconds() < 0 {
-                    return "000000".to_string();
-                }
-                let hours = duration.num_hours();
-                let minutes = duration.num_minutes() % 60;
-                let seconds = duration.num_seconds() % 60;
-                format!("{:02}{:02}{:02}", hours, minutes, seconds)
+// Speed up of the fast fall, unless given explicitly
+const DEFAULT_FAST_FALL_FACTOR: u32 = 4;
+// Like the fall speed, the speed up is bounded to keep the updates cheap
+const MAX_FAST_FALL_FACTOR: u32 = 100;
+
+fn parse_animation(animation: &str) -> Result<AnimationPolicy> {
+    match animation.split_once(':') {
+        None if animation == "fall" => Ok(AnimationPolicy::Fall),
+        None if animation == "instant" => Ok(AnimationPolicy::Instant),
+        None if animation == "fast" => Ok(AnimationPolicy::FastFall(DEFAULT_FAST_FALL_FACTOR)),
+        Some(("fast", factor)) => match factor.parse::<u32>() {
+            Ok(factor) if (1..=MAX_FAST_FALL_FACTOR).contains(&factor) => {
+                Ok(AnimationPolicy::FastFall(factor))
             }
-            Self::Stopwatch(start) => {
-                let duration = Local::now().signed_duration_since(*start);
-                let hours = duration.num_hours();
-                let minutes = duration.num_minutes() % 60;
-                let seconds = duration.num_seconds() % 60;
-                format!("{:02}{:02}{:02}", hours, minutes, seconds)
+            _ => bail!(
+                "expected a positive factor of at most {}, eg. \"fast:4\"",
+                MAX_FAST_FALL_FACTOR
+            ),
+        },
+        _ => bail!("expected \"fall\", \"instant\" or \"fast[:FACTOR]\""),
+    }
+}
+
+// Tetrominos move one cell at a time, so faster speeds only cost time
+// without being visible any more
+const MAX_FALL_SPEED: f64 = 10_000.0;
+
+fn parse_fall_speed(speed: &str) -> Result<f64> {
+    match speed.parse::<f64>() {
+        Ok(speed) if speed > 0.0 && speed <= MAX_FALL_SPEED => Ok(speed),
+        _ => bail!("expected a positive number of at most {}", MAX_FALL_SPEED),
+    }
+}
+
+// Beyond this a tetromino crosses any terminal within a few frames anyway
+const MAX_GRAVITY: f64 = 10_000.0;
+
+fn parse_gravity(gravity: &str) -> Result<f64> {
+    match gravity.parse::<f64>() {
+        Ok(gravity) if (0.0..=MAX_GRAVITY).contains(&gravity) => Ok(gravity),
+        _ => bail!("expected a number from 0 to {}", MAX_GRAVITY),
+    }
+}
+
+// pixel_loop restores the terminal and exits the process on its own, without
+// ever returning to main. The laps are therefore exported by an exit handler,
+// which writes the CSV prepared whenever the laps changed.
+static LAP_EXPORT: Mutex<Option<(String, Option<PathBuf>)>> = Mutex::new(None);
+
+extern "C" fn export_laps() {
+    let Ok(lap_export) = LAP_EXPORT.lock() else {
+        return;
+    };
+    match lap_export.as_ref() {
+        Some((csv, Some(path))) => {
+            if let Err(err) = std::fs::write(path, csv) {
+                eprintln!("Could not write laps to '{}': {}", path.display(), err);
             }
         }
+        Some((csv, None)) => {
+            let mut stdout = std::io::stdout();
+            let _ = stdout
+                .write_all(csv.as_bytes())
+                .and_then(|_| stdout.flush());
+        }
+        None => {}
+    }
+}
+
+// Errors can't be printed while pixel_loop controls the terminal, so the
+// first failure of the on-finish command is reported once it exited
+static NOTIFY_ERROR: Mutex<Option<String>> = Mutex::new(None);
+
+extern "C" fn report_notify_error() {
+    if let Ok(notify_error) = NOTIFY_ERROR.lock() {
+        if let Some(message) = notify_error.as_ref() {
+            eprintln!("{}", message);
+        }
+    }
+}
+
+// Durations are exported as HH:MM:SS.mmm
+fn format_csv_duration(duration: Duration) -> String {
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        duration.num_hours(),
+        duration.num_minutes() % 60,
+        duration.num_seconds() % 60,
+        duration.num_milliseconds() % 1000
+    )
+}
+
+fn laps_to_csv(laps: &[Lap]) -> String {
+    let mut csv = String::from("lap,lap_time,split_time\n");
+    for lap in laps {
+        csv += &format!(
+            "{},{},{}\n",
+            lap.number,
+            format_csv_duration(lap.time),
+            format_csv_duration(lap.split)
+        );
     }
+    csv
 }
 
 struct State {
-    digit_boards: Vec<DigitBoard>,
-    current_digits: Vec<Digit>,
-    seperator_boards: Vec<Board>,
-    last_update_time: DateTime<Local>,
-    colorscheme: Colorscheme,
-    mode: Mode,
+    clock: Clock,
+    // The loop updates with a fixed rate, so every update advances the
+    // animation by the same time
+    frame_time: StdDuration,
+    scale: Scale,
+    on_finish: Option<String>,
+    exit_on_finish: Option<i32>,
+    bell: bool,
+    laps_file: Option<PathBuf>,
+    // On-finish commands still running, reaped once they exited
+    children: Vec<Child>,
 }
 
 impl State {
-    fn new(mode: Mode, colorscheme: Colorscheme) -> Self {
-        Self {
-            digit_boards: vec![],
-            current_digits: vec![],
-            seperator_boards: vec![],
-            last_update_time: Local::now(),
-            mode,
-            colorscheme,
-        }
-    }
-
-    // fn initialize_time(&mut self, width: u32, height: u32) {
-    //     // Each digit is 6x10 pixels
-    //     // Spacing 2 between each
-    //     // There are 6 digits
-    //     // -> height: 10
-    //     // -> width: 6*6 + 5*2
-    //     let x_start = (width as i64 - 6*6 - 5*2)/2;
-    //     let y_stop = (height as i64 + 10)/2; 
-    //     let now = Local::now();
-    //     let digits = time_to_digits(now);
-
-    //     self.digit_boards = digits
-    //         .iter()
-    //         .cloned()
-    //         .enumerate()
-    //         .map(|(i, digit)| DigitBoard::new(x_start + (i as i64 * 8), y_stop, digit.into()))
-    //         .collect();
-
-    //     self.current_digits = digits;
-    // }
-
-    fn resize_canvas(&mut self, width: u32, height: u32) {
-        // Each digit is 6x10
-        // Spacing 2 between each
-        // There are 6 digits
-        // -> height: 10
-        // -> width: 6*6 + 5*2
-        let x_start = (width as i64 - 6 * 6 - 3 * 2 - 6*2) / 2;
-        let y_stop = (height as i64 + 10) / 2;
-        let now = Local::now();
-        // let digits = time_string_to_digits(self.mode.get_timestring());
-        let digits = time_to_digits(now);
-        let colorscheme = self.colorscheme.clone();
-        self.digit_boards = digits
-            .iter()
-            .cloned()
-            .enumerate()
-            .map(|(i, digit)| {
-                // @TODO: This is ugly as hell, but it is late and my brain
-                // doesn't want to come up with something nicer here at the
-                // moment ;)
-                let x = x_start
-                    + match i {
-                        0 => 0,
-                        1 => 6 + 2,
-                        2 => 6 + 2 + 6 + 6,
-                        3 => 6 + 2 + 6 + 6 + 6 + 2,
-                        4 => 6 + 2 + 6 + 6 + 6 + 2 + 6 + 6,
-                        5 => 6 + 2 + 6 + 6 + 6 + 2 + 6 + 6 + 6 + 2,
-                        _ => panic!("unknown digit position {}", i),
-                    };
-                DigitBoard::new(i, x, y_stop, colorscheme, digit.into())
-            })
-            .collect();
-
-        // self.digit_boards = digits
-        // .iter()
-        // .cloned()
-        // .enumerate()
-        // .map(|(i, digit)| DigitBoard::new(i,x_start + (i as i64 * 8), y_stop, colorscheme, digit.into()))
-        // .collect();
-
-        self.current_digits = digits;
-        self.seperator_boards = vec![
-            // @TODO: This is ugly as hell, but it is late and my brain
-            // doesn't want to come up with something nicer here at the
-            // moment ;)
-            Board::new(x_start + (6 + 2 + 6 + 2), 0, y_stop - 2),
-            Board::new(x_start + (6 + 2 + 6 + 2), -4, y_stop - 6),
-            Board::new(x_start + (6 + 2 + 6 + 6 + 6 + 2 + 6 + 2), 0, y_stop - 2),
-            Board::new(x_start + (6 + 2 + 6 + 6 + 6 + 2 + 6 + 2), -4, y_stop - 6),
-        ];
+    fn record_lap(&mut self) {
+        if self.clock.record_lap().is_some() {
+            self.update_lap_export();
+        }
+    }
 
-        let color = self.colorscheme.apply(Shape::O, Digit::Zero, 0);
-        for board in self.seperator_boards.iter_mut() {
-            board.add_tetromino(0, 0, color, Shape::O, Rotation::NoRotation);
+    fn reset(&mut self) {
+        self.clock.reset();
+        self.update_lap_export();
+    }
+
+    fn update_lap_export(&self) {
+        let laps = self.clock.mode().laps();
+        let export = (!laps.is_empty()).then(|| (laps_to_csv(&laps), self.laps_file.clone()));
+        if let Ok(mut lap_export) = LAP_EXPORT.lock() {
+            *lap_export = export;
         }
     }
 
+    fn resize(&mut self, width: u32, height: u32) {
+        if let Scale::Auto = self.scale {
+            let scale = self.clock.fit_scale(width, height);
+            self.clock.set_scale(scale);
+        }
+        self.clock.center_on(width, height);
+    }
 
-    // fn update_time(&mut self, now: DateTime<Local>) {
-    //     let digits = time_to_digits(now);
-    //     for (i, board) in self.digit_boards.iter_mut().enumerate() {
-    //         if self.current_digits[i] != digits[i] {
-    //             board.set_digit(digits[i]);    
-    //         }                    
-    //     }
-    //     self.current_digits = digits;
-    // }
+    // Both a countdown and an interval after its last round finish
+    fn timer_finished(&mut self) -> NextLoopState {
+        self.notify();
+        match self.exit_on_finish {
+            Some(code) => NextLoopState::Exit(code),
+            None => NextLoopState::Continue,
+        }
+    }
+
+    // Ring the bell and run the on-finish command. Failures must not stop the
+    // loop, a missing bell is ignored and a failing command reported on exit.
+    fn notify(&mut self) {
+        if self.bell {
+            let mut stdout = std::io::stdout();
+            let _ = stdout.write_all(b"\x07").and_then(|_| stdout.flush());
+        }
 
-     fn update_time(&mut self, digits: Vec<Digit>) {
-        for (i, board) in self.digit_boards.iter_mut().enumerate() {
-            if self.current_digits[i] != digits[i] {
-                board.set_digit(digits[i]);
+        if let Some(command) = &self.on_finish {
+            // The command is not waited for, to keep the animation going. Its
+            // output would end up on top of the clock, so it is discarded.
+            let (shell, flag) = if cfg!(windows) {
+                ("cmd", "/C")
+            } else {
+                ("sh", "-c")
+            };
+            let spawned = Command::new(shell)
+                .args([flag, command])
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn();
+            match spawned {
+                Ok(child) => self.children.push(child),
+                Err(err) => {
+                    if let Ok(mut notify_error) = NOTIFY_ERROR.lock() {
+                        notify_error.get_or_insert_with(|| {
+                            format!("Could not run the on-finish command '{}': {}", command, err)
+                        });
+                    }
+                }
             }
         }
-        self.current_digits = digits;
+    }
+
+    // Collect the exit status of finished on-finish commands, so they don't
+    // linger as zombies until tetrotime exits
+    fn reap_children(&mut self) {
+        self.children
+            .retain_mut(|child| matches!(child.try_wait(), Ok(None)));
     }
 }
 
-#[derive(Parser, Debug)]
-#[command(
-    author = "Tutorial From Jakob Westhoff <jakob@westhoffswelt.de>",
-    about = "Tutorial From TetroTime - Time meets Tetris!"
-)]
-struct Args {
-    #[arg(short = 'c', long, group = "mode", help = "Show a clock")]
-    clock: bool,
-    #[arg(short = 'w', long, group = "mode", help = "Show a stopwatch")]
-    stopwatch: bool,
-    #[arg(
-        short = 'd',
-        long,
-        group = "mode",
-        help = "Show a countdown (Duration in HHMMSS or HH:MM:SS)",
-        value_name = "DURATION"
-    )]
-    countdown: Option<String>,
-    #[arg(short='s', long, value_enum, default_value_t = Colorscheme::default(), help = "Select a specific colorscheme")]
-    colorscheme: Colorscheme,
+// Parse a duration given as HH:MM:SS or HHMMSS. The hours are not limited to
+// a single day, so 72:00:00 is a valid three day countdown.
+fn parse_duration(duration: &str) -> Result<Duration> {
+    let invalid = || anyhow!("Invalid duration '{}'. Expected HHMMSS or HH:MM:SS", duration);
+
+    let (hours, minutes, seconds) = match duration.split(':').collect::<Vec<_>>()[..] {
+        [hours, minutes, seconds] => (hours, minutes, seconds),
+        [digits] if digits.len() >= 6 && digits.is_ascii() => {
+            let (hours, rest) = digits.split_at(digits.len() - 4);
+            let (minutes, seconds) = rest.split_at(2);
+            (hours, minutes, seconds)
+        }
+        _ => return Err(invalid()),
+    };
+
+    let parse = |value: &str| value.parse::<i64>().map_err(|_| invalid());
+    let (hours, minutes, seconds) = (parse(hours)?, parse(minutes)?, parse(seconds)?);
+    if hours < 0 || !(0..60).contains(&minutes) || !(0..60).contains(&seconds) {
+        return Err(invalid());
+    }
+
+    Ok(Duration::hours(hours) + Duration::minutes(minutes) + Duration::seconds(seconds))
+}
+
+fn naive_to_local(naive: NaiveDateTime) -> Result<DateTime<Local>> {
+    naive
+        .and_local_timezone(Local)
+        .earliest()
+        .ok_or_else(|| anyhow!("Time '{}' does not exist in the local time zone", naive))
+}
+
+// Parse a countdown target. A time without a date refers to its next
+// occurrence, which might be tomorrow.
+fn parse_target(target: &str, now: DateTime<Local>) -> Result<DateTime<Local>> {
+    for format in [
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M",
+    ] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(target, format) {
+            return naive_to_local(naive);
+        }
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(target, "%Y-%m-%d") {
+        return naive_to_local(date.and_time(NaiveTime::MIN));
+    }
+
+    for format in ["%H:%M:%S", "%H:%M"] {
+        if let Ok(time) = NaiveTime::parse_from_str(target, format) {
+            let mut date = now.date_naive();
+            if time <= now.time() {
+                date = date
+                    .succ_opt()
+                    .context("Can not count down beyond the end of time")?;
+            }
+            return naive_to_local(date.and_time(time));
+        }
+    }
+
+    bail!(
+        "Invalid target '{}'. Expected HH:MM, HH:MM:SS, YYYY-MM-DD or YYYY-MM-DDTHH:MM:SS",
+        target
+    )
+}
+
+// Resolve a named clock format or validate a custom strftime pattern
+fn parse_clock_format(format: &str) -> Result<String> {
+    let pattern = match format {
+        "24h" => FORMAT_24H,
+        "24h-short" => FORMAT_24H_SHORT,
+        "12h" => FORMAT_12H,
+        "12h-short" => FORMAT_12H_SHORT,
+        pattern if pattern.contains('%') => pattern,
+        _ => bail!(
+            "Unknown format '{}'. Use 24h, 24h-short, 12h, 12h-short or a strftime pattern",
+            format
+        ),
+    };
+    validate_pattern(pattern)
+}
+
+// Date order of the current locale (LC_ALL, LC_TIME or LANG), based on the
+// territory part of the locale name (eg. "en_US.UTF-8")
+fn locale_date_format() -> &'static str {
+    let locale = ["LC_ALL", "LC_TIME", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+    let territory = locale
+        .split(['.', '@'])
+        .next()
+        .and_then(|name| name.split_once('_'))
+        .map(|(_, territory)| territory);
+    match territory {
+        None => FORMAT_DATE_YMD,
+        Some("US" | "PH" | "FM" | "MH" | "PW") => FORMAT_DATE_MDY,
+        Some("CN" | "JP" | "KR" | "TW" | "HU" | "LT" | "MN" | "SE") => FORMAT_DATE_YMD,
+        Some(_) => FORMAT_DATE_DMY,
+    }
+}
+
+fn parse_date_format(format: &str) -> Result<String> {
+    let pattern = match format {
+        "ymd" => FORMAT_DATE_YMD,
+        "dmy" => FORMAT_DATE_DMY,
+        "mdy" => FORMAT_DATE_MDY,
+        "locale" => locale_date_format(),
+        pattern if pattern.contains('%') => pattern,
+        _ => bail!(
+            "Unknown date format '{}'. Use ymd, dmy, mdy, locale or a strftime pattern",
+            format
+        ),
+    };
+    validate_pattern(pattern)
+}
+
+// Make sure the pattern is valid and every character it produces can be
+// displayed
+fn validate_pattern(pattern: &str) -> Result<String> {
+    let mut sample = String::new();
+    write!(sample, "{}", Local::now().format(pattern))
+        .map_err(|_| anyhow!("Invalid strftime pattern '{}'", pattern))?;
+    if let Err(c) = Glyph::parse(&sample) {
+        bail!(
+            "Format '{}' produces the character '{}', which can not be displayed",
+            pattern,
+            c
+        );
+    }
+
+    Ok(pattern.to_string())
+}
+
+// Interval segments are short, so besides the countdown formats plain
+// seconds and MM:SS are accepted as well
+fn parse_segment_duration(duration: &str) -> Result<Duration> {
+    let invalid = || {
+        anyhow!(
+            "Invalid duration '{}'. Expected seconds, MM:SS or HH:MM:SS",
+            duration
+        )
+    };
+    let parse = |value: &str| value.parse::<i64>().map_err(|_| invalid());
+
+    match duration.split(':').collect::<Vec<_>>()[..] {
+        [seconds] if seconds.len() < 6 => Ok(Duration::seconds(parse(seconds)?)),
+        [minutes, seconds] => {
+            let (minutes, seconds) = (parse(minutes)?, parse(seconds)?);
+            if minutes < 0 || !(0..60).contains(&seconds) {
+                return Err(invalid());
+            }
+            Ok(Duration::minutes(minutes) + Duration::seconds(seconds))
+        }
+        _ => parse_duration(duration),
+    }
+}
+
+// A segment of an interval, given as DURATION or LABEL=DURATION
+fn parse_segment(segment: &str) -> Result<Segment> {
+    let segment = segment.trim();
+    let (label, duration) = segment.split_once('=').unwrap_or(("", segment));
+    if let Err(c) = Glyph::parse(label) {
+        bail!(
+            "Label '{}' contains the character '{}', which can not be displayed",
+            label,
+            c
+        );
+    }
+    let duration = parse_segment_duration(duration)?;
+    if duration <= Duration::zero() {
+        bail!("Segment '{}' is shorter than a second", segment);
+    }
+    Ok(Segment::new(label, duration))
+}
+
+// One segment per line. Empty lines and lines starting with # are ignored.
+fn read_segments(path: &Path) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Could not read interval file '{}'", path.display()))?;
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+fn interval_mode(segments: &[String], rounds: u32) -> Result<Mode> {
+    let segments = segments
+        .iter()
+        .map(|segment| parse_segment(segment))
+        .collect::<Result<Vec<_>>>()?;
+    if segments.is_empty() {
+        bail!("An interval needs at least one segment");
+    }
+    Ok(Mode::Interval(Interval::new(segments, rounds)))
+}
+
+fn countdown_mode(duration: Duration) -> Result<Mode> {
+    if duration >= Duration::days(100) {
+        bail!("Countdowns are limited to less than 100 days");
+    }
+    Ok(Mode::countdown(duration))
+}
+
+// Each line of the text becomes a row of glyphs
+fn text_mode(text: &str) -> Result<Mode> {
+    for line in text.lines() {
+        if let Err(c) = Glyph::parse(line) {
+            bail!(
+                "Text '{}' contains the character '{}', which can not be displayed",
+                line,
+                c
+            );
+        }
+    }
+    if text.trim().is_empty() {
+        bail!("The text must not be empty");
+    }
+    Ok(Mode::Text(text.to_string()))
+}
+
+fn parse_tz(name: &str) -> Result<Tz> {
+    name.parse()
+        .map_err(|_| anyhow!("Unknown time zone '{}' (eg. Europe/Berlin)", name))
+}
+
+// A zone of the world clock, given as ZONE or LABEL=ZONE
+fn parse_zone(zone: &str) -> Result<Zone> {
+    let Some((label, name)) = zone.split_once('=') else {
+        return Ok(Zone::new(parse_tz(zone)?, ZoneLabel::Offset));
+    };
+    if let Err(c) = Glyph::parse(label) {
+        bail!(
+            "Label '{}' contains the character '{}', which can not be displayed",
+            label,
+            c
+        );
+    }
+    let label = ZoneLabel::Text(label.to_string());
+    Ok(Zone::new(parse_tz(name)?, label))
+}
+
+// Clock or date mode, shown in the given time zone (if any)
+fn clock_mode(format: String, tz: Option<&str>) -> Result<Mode> {
+    match tz {
+        Some(name) => {
+            let zone = Zone::new(parse_tz(name)?, ZoneLabel::None);
+            Ok(Mode::WorldClock(format, vec![zone]))
+        }
+        None => Ok(Mode::Clock(format)),
+    }
 }
 
 fn get_mode_from_args(args: &Args) -> Result<Mode> {
-    if args.clock {
-        Ok(Mode::Clock)
+    let tz = args.tz.as_deref();
+    if args.date {
+        clock_mode(parse_date_format(&args.date_format)?, tz)
+    } else if let Some(world) = &args.world {
+        let zones = world
+            .iter()
+            .map(|zone| parse_zone(zone))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Mode::WorldClock(parse_clock_format(&args.format)?, zones))
     } else if args.stopwatch {
-        Ok(Mode::Stopwatch(Local::now()))
-    } else if let Some(countdown) = &args.countdown {
-        let time = NaiveTime::parse_from_str(countdown, "%H:%M:%S").unwrap_or_else(|_| {
-            NaiveTime::parse_from_str(countdown, "%H%M%S")
-                .unwrap_or(NaiveTime::from_num_seconds_from_midnight_opt(0, 0).unwrap())
-        });
-        Ok(Mode::Countdown(
-            Local::now() + Duration::seconds(time.num_seconds_from_midnight() as i64),
+        Ok(Mode::Stopwatch(
+            Stopwatch::new().with_precision(args.precision),
         ))
+    } else if let Some(segments) = &args.interval {
+        interval_mode(segments, args.rounds)
+    } else if let Some(path) = &args.interval_file {
+        interval_mode(&read_segments(path)?, args.rounds)
+    } else if args.pomodoro {
+        let minutes = |minutes: u32| Duration::minutes(minutes as i64);
+        Ok(Mode::Pomodoro(Pomodoro::new(
+            minutes(args.work),
+            minutes(args.short_break),
+            minutes(args.long_break),
+            args.cycles,
+        )))
+    } else if let Some(countdown) = &args.countdown {
+        countdown_mode(parse_duration(countdown)?)
+    } else if let Some(until) = &args.until {
+        let now = Local::now();
+        let target = parse_target(until, now)?;
+        if target <= now {
+            bail!("Target '{}' lies in the past", until);
+        }
+        countdown_mode(target.signed_duration_since(now))
+    } else if let Some(text) = &args.text {
+        text_mode(text)
+    } else if args.with_date {
+        let time = parse_clock_format(&args.format)?;
+        let date = parse_date_format(&args.date_format)?;
+        clock_mode(format!("{}\n{}", time, date), tz)
     } else {
-        Ok(Mode::default())
+        clock_mode(parse_clock_format(&args.format)?, tz)
     }
 }
 
-fn main() -> Result<()> {
+// Print the rows of the bitmaps next to each other, indented
+fn side_by_side(bitmaps: &[&Bitmap]) -> String {
+    let height = bitmaps
+        .iter()
+        .map(|bitmap| bitmap.height())
+        .max()
+        .unwrap_or(0);
+    let arts: Vec<Vec<String>> = bitmaps
+        .iter()
+        .map(|bitmap| bitmap.to_string().lines().map(String::from).collect())
+        .collect();
+    (0..height)
+        .map(|y| {
+            let row: Vec<String> = arts
+                .iter()
+                .zip(bitmaps)
+                .map(|(art, bitmap)| {
+                    let line = art.get(y).map_or("", String::as_str);
+                    format!("{:width$}", line, width = bitmap.width())
+                })
+                .collect();
+            format!("    {}", row.join("   ")).trim_end().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
 
-    // let (terminal_width, terminal_height) = terminal::size()?;
-    // let width = terminal_width;
-    // let height = terminal_height * 2;
+// Glyphs without a bitmap are printed as they settled, ready to be pasted
+// into the `[bitmaps]` table of the font file
+fn glyph_check(path: &Path) -> Result<bool> {
+    let font = Arc::new(Font::load(path)?);
+    if font.listed_glyphs().is_empty() {
+        bail!(
+            "The font file '{}' does not list any glyphs",
+            path.display()
+        );
+    }
+    let mut passed = true;
+    for &(c, glyph) in font.listed_glyphs() {
+        let settled = simulator::settle(&font, glyph);
+        let fits =
+            settled.width() as i64 == glyph.width() && settled.height() as i64 == GLYPH_HEIGHT;
+        match font.bitmap(glyph) {
+            Some(expected) if *expected == settled => println!("'{}': ok", c),
+            Some(expected) => {
+                passed = false;
+                println!("'{}': mismatch (expected / settled)", c);
+                println!("{}", side_by_side(&[expected, &settled]));
+            }
+            None if !fits => {
+                passed = false;
+                println!("'{}': tetrominos stick out of the glyph", c);
+                println!("{}", side_by_side(&[&settled]));
+            }
+            None => {
+                println!("'{}': no bitmap, settles into", c);
+                println!("{}", side_by_side(&[&settled]));
+            }
+        }
+    }
+    Ok(passed)
+}
+
+// The found tetrominos are dropped on an in-memory board once more, to make
+// sure they settle into the bitmap just like on screen
+fn solve(c: char, path: &Path, rust: bool) -> Result<()> {
+    let glyph = Glyph::from_char(c)
+        .filter(Glyph::is_animated)
+        .ok_or_else(|| anyhow!("'{}' is neither a digit nor a letter", c))?;
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Could not read bitmap file '{}'", path.display()))?;
+    let target: Bitmap = content
+        .parse()
+        .with_context(|| format!("Invalid bitmap file '{}'", path.display()))?;
+    if target.width() as i64 != glyph.width() || target.height() as i64 != GLYPH_HEIGHT {
+        bail!(
+            "The bitmap of '{}' must be {}x{} cells. Got {}x{}",
+            c,
+            glyph.width(),
+            GLYPH_HEIGHT,
+            target.width(),
+            target.height()
+        );
+    }
+
+    let animation =
+        solver::solve(&target).ok_or_else(|| anyhow!("No tetrominos can build the bitmap"))?;
+    let mut font = Font::default();
+    font.set_animation(glyph, animation.clone());
+    let settled = simulator::settle(&Arc::new(font), glyph);
+    if settled != target {
+        bail!(
+            "The found tetrominos settle differently:\n{}",
+            side_by_side(&[&target, &settled])
+        );
+    }
 
+    if rust {
+        for tetromino in &animation {
+            println!(
+                "FallingTetromino::new({:?}, {:?}, {}),",
+                tetromino.shape, tetromino.rotation, tetromino.dx
+            );
+        }
+    } else {
+        println!("{}", Font::to_toml_entry(c, &animation));
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
     let args = Args::parse();
 
-    let mode = get_mode_from_args(&args)?;
+    match &args.action {
+        Some(Action::GlyphCheck { font }) => {
+            if !glyph_check(font)? {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Action::Solve {
+            glyph,
+            bitmap,
+            rust,
+        }) => return solve(*glyph, bitmap, *rust),
+        None => {}
+    }
 
+    let mode = get_mode_from_args(&args)?;
 
     let canvas = CrosstermCanvas::new();
     let input = CrosstermInputState::new();
 
-    let mut state = State::new(mode, args.colorscheme);
-    state.resize_canvas(canvas.width(), canvas.height());
-    // state.initialize_time(canvas.width(), canvas.height());
-    // state.resize_canvas(canvas.width(), canvas.height());
-    
+    let mut clock = Clock::new(mode, args.colorscheme);
+    clock.set_tick(args.tick);
+    clock.set_animation_policy(args.animation);
+    clock.set_diffing(args.keep_shared);
+    clock.set_transition(args.transition);
+    if let Some(path) = &args.font {
+        clock.set_font(Font::load(path)?);
+    }
+    clock.set_physics(Physics {
+        fall_speed: args.fall_speed,
+        spawn_interval: StdDuration::from_millis(args.spawn_interval),
+        gravity: args.gravity,
+    });
+    clock.set_spacing(args.spacing);
+    if let Scale::Fixed(scale) = args.scale {
+        clock.set_scale(scale);
+    }
+    let phase_colorschemes = [
+        (Phase::Work, args.work_colorscheme),
+        (Phase::ShortBreak, args.short_break_colorscheme),
+        (Phase::LongBreak, args.long_break_colorscheme),
+    ];
+    for (phase, colorscheme) in phase_colorschemes {
+        if let Some(colorscheme) = colorscheme {
+            clock.set_phase_colorscheme(phase, colorscheme);
+        }
+    }
+
+    let mut state = State {
+        clock,
+        frame_time: StdDuration::from_secs(1) / args.fps,
+        scale: args.scale,
+        on_finish: args.on_finish,
+        exit_on_finish: args.exit_on_finish,
+        bell: !args.no_bell,
+        laps_file: args.laps_file,
+        children: vec![],
+    };
+    // SAFETY: The handlers are plain functions without arguments, which only
+    // access synchronized global state.
+    if state.on_finish.is_some() {
+        unsafe {
+            libc::atexit(report_notify_error);
+        }
+    }
+    if let Mode::Stopwatch(..) = state.clock.mode() {
+        unsafe {
+            libc::atexit(export_laps);
+        }
+    }
+    state.resize(canvas.width(), canvas.height());
 
-    // eprintln!("Render size: {}x{}", width, height);
     eprintln!("Render size: {}x{}", canvas.width(), canvas.height());
 
     pixel_loop::run(
-        60,
+        args.fps as usize,
         state,
         input,
         canvas,
-        |e, s, input, canvas| {
-
+        |_e, s, input, canvas| {
             if let Some((width, height)) = canvas.did_resize() {
-                s.resize_canvas(width, height);
-            }           
+                s.resize(width, height);
+            }
 
             if input.is_key_pressed(KeyboardKey::Q) {
                 return Ok(NextLoopState::Exit(0));
             }
 
-            // if input.is_key_pressed(KeyboardKey::Space) {
-            //     // s.board.set_digit(Digit::One);
-            //     // s.current_digit += 1;
-            //     // if (s.current_digit > 9) {
-            //     //     s.current_digit = 0;
-            //     // }
-
-            //     // s.board.set_digit(s.current_digit.into());
-            // }
-            
-            for board in s.digit_boards.iter_mut() {
-                board.update(canvas);
+            if input.is_key_pressed(KeyboardKey::Space) {
+                s.clock.toggle_pause();
+            }
+
+            if input.is_key_pressed(KeyboardKey::R) {
+                s.reset();
             }
 
-            for board in s.seperator_boards.iter_mut() {
-                board.update(canvas);
+            if input.is_key_pressed(KeyboardKey::L) {
+                s.record_lap();
             }
 
-            let now = Local::now();
-            if now.signed_duration_since(s.last_update_time) > Duration::seconds(3) {
-                s.update_time(time_string_to_digits(s.mode.get_timestring()));
-                s.last_update_time = now;
+            s.reap_children();
+            match s.clock.update(canvas, s.frame_time) {
+                Some(ClockEvent::Finished) => return Ok(s.timer_finished()),
+                Some(ClockEvent::PhaseChanged(_) | ClockEvent::SegmentStarted(_)) => s.notify(),
+                None => {}
             }
-           
 
-           
             Ok(NextLoopState::Continue)
         },
-        |e, s, i, canvas, dt| {
+        |_e, s, _i, canvas, _dt| {
             // RENDER BEGIN
             canvas.clear_screen(&Color::from_rgb(0, 0, 0));
 
-            for board in s.digit_boards.iter() {
-                board.render(canvas);
-            }
+            s.clock.render(canvas);
 
-            for board in s.seperator_boards.iter() {
-                board.render(canvas);
-            }
-            
-            
             // RENDER END
 
             canvas.render()?;
@@ -316,3 +1080,205 @@ fn main() -> Result<()> {
         },
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn local(hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 3, 1, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn tz_only_applies_to_clock_and_date() {
+        for mode in [["--date"], ["--clock"]] {
+            assert!(Args::try_parse_from(["tetrotime", "--tz", "UTC", mode[0]]).is_ok());
+        }
+        for mode in [
+            &["-w"][..],
+            &["-d", "10"],
+            &["-u", "12:00"],
+            &["--world", "UTC"],
+            &["-p"],
+            &["-i", "10"],
+            &["--interval-file", "segments"],
+            &["-t", "HI"],
+        ] {
+            let args = ["tetrotime", "--tz", "UTC"].iter().chain(mode);
+            assert!(Args::try_parse_from(args).is_err(), "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn with_date_only_applies_to_the_clock() {
+        assert!(Args::try_parse_from(["tetrotime", "--with-date"]).is_ok());
+        assert!(Args::try_parse_from(["tetrotime", "--with-date", "--tz", "UTC"]).is_ok());
+        for mode in [
+            &["--date"][..],
+            &["-w"],
+            &["-d", "10"],
+            &["-u", "12:00"],
+            &["--world", "UTC"],
+            &["-p"],
+            &["-i", "10"],
+            &["--interval-file", "segments"],
+            &["-t", "HI"],
+        ] {
+            let args = ["tetrotime", "--with-date"].iter().chain(mode);
+            assert!(Args::try_parse_from(args).is_err(), "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn fall_speed_is_capped() {
+        assert_eq!(parse_fall_speed("60").unwrap(), 60.0);
+        assert_eq!(parse_fall_speed("10000").unwrap(), MAX_FALL_SPEED);
+        for speed in ["0", "-1", "10001", "1e12", "inf", "NaN", "fast"] {
+            assert!(parse_fall_speed(speed).is_err(), "{:?}", speed);
+        }
+        assert!(parse_animation("fast:100").is_ok());
+        assert!(parse_animation("fast:101").is_err());
+    }
+
+    #[test]
+    fn fps_is_bounded() {
+        for fps in ["1", "60", "240"] {
+            let args = Args::try_parse_from(["tetrotime", "--fps", fps]).unwrap();
+            assert_eq!(args.fps.to_string(), fps);
+        }
+        for fps in ["0", "241", "4294967295", "-1"] {
+            assert!(
+                Args::try_parse_from(["tetrotime", "--fps", fps]).is_err(),
+                "{:?}",
+                fps
+            );
+        }
+    }
+
+    #[test]
+    fn gravity_is_capped() {
+        assert_eq!(parse_gravity("0").unwrap(), 0.0);
+        assert_eq!(parse_gravity("10000").unwrap(), MAX_GRAVITY);
+        for gravity in ["-1", "10001", "1e15", "inf", "NaN", "fast"] {
+            assert!(parse_gravity(gravity).is_err(), "{:?}", gravity);
+        }
+    }
+
+    #[test]
+    fn duration_with_colons() {
+        assert_eq!(parse_duration("00:25:00").unwrap(), Duration::minutes(25));
+        assert_eq!(parse_duration("72:00:00").unwrap(), Duration::hours(72));
+        assert_eq!(parse_duration("1:02:03").unwrap(), Duration::seconds(3723));
+    }
+
+    #[test]
+    fn duration_without_colons() {
+        assert_eq!(parse_duration("013000").unwrap(), Duration::minutes(90));
+        assert_eq!(parse_duration("1000000").unwrap(), Duration::hours(100));
+    }
+
+    #[test]
+    fn invalid_durations() {
+        for duration in [
+            "", "12:00", "1:2:3:4", "00:60:00", "00:00:60", "-1:00:00", "12345", "aa:bb:cc",
+            "0a3000",
+        ] {
+            assert!(parse_duration(duration).is_err(), "{:?}", duration);
+        }
+    }
+
+    #[test]
+    fn target_later_today() {
+        assert_eq!(parse_target("12:30", local(10, 0)).unwrap(), local(12, 30));
+        assert_eq!(
+            parse_target("10:00:30", local(10, 0)).unwrap().time(),
+            NaiveTime::from_hms_opt(10, 0, 30).unwrap()
+        );
+    }
+
+    #[test]
+    fn past_target_rolls_to_tomorrow() {
+        let target = parse_target("08:00", local(10, 0)).unwrap();
+        assert_eq!(target, local(8, 0) + Duration::days(1));
+        let target = parse_target("10:00", local(10, 0)).unwrap();
+        assert_eq!(target, local(10, 0) + Duration::days(1));
+    }
+
+    #[test]
+    fn target_with_date() {
+        assert_eq!(
+            parse_target("2024-03-02T08:15", local(10, 0)).unwrap(),
+            local(8, 15) + Duration::days(1)
+        );
+        assert_eq!(
+            parse_target("2024-03-05", local(10, 0)).unwrap(),
+            local(0, 0) + Duration::days(4)
+        );
+    }
+
+    #[test]
+    fn invalid_targets() {
+        for target in ["", "25:00", "noon", "2024-13-01", "12:00 tomorrow"] {
+            assert!(parse_target(target, local(10, 0)).is_err(), "{:?}", target);
+        }
+    }
+
+    #[test]
+    fn laps_as_csv() {
+        let laps = [
+            Lap {
+                number: 1,
+                time: Duration::milliseconds(65_250),
+                split: Duration::milliseconds(65_250),
+            },
+            Lap {
+                number: 2,
+                time: Duration::milliseconds(3_723_456),
+                split: Duration::milliseconds(3_788_706),
+            },
+            Lap {
+                number: 3,
+                time: Duration::milliseconds(7),
+                split: Duration::hours(27) + Duration::milliseconds(7),
+            },
+        ];
+        assert_eq!(
+            laps_to_csv(&laps),
+            "lap,lap_time,split_time\n\
+             1,00:01:05.250,00:01:05.250\n\
+             2,01:02:03.456,01:03:08.706\n\
+             3,00:00:00.007,27:00:00.007\n"
+        );
+        assert_eq!(laps_to_csv(&[]), "lap,lap_time,split_time\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn finished_on_finish_commands_are_reaped() {
+        let mut state = State {
+            clock: Clock::new(Mode::default(), Colorscheme::default()),
+            frame_time: StdDuration::from_millis(16),
+            scale: Scale::Fixed(1),
+            on_finish: Some("echo finished".to_string()),
+            exit_on_finish: None,
+            bell: false,
+            laps_file: None,
+            children: vec![],
+        };
+        state.notify();
+        state.notify();
+        assert_eq!(state.children.len(), 2);
+
+        for _ in 0..500 {
+            state.reap_children();
+            if state.children.is_empty() {
+                break;
+            }
+            std::thread::sleep(StdDuration::from_millis(10));
+        }
+        assert!(state.children.is_empty());
+        assert!(NOTIFY_ERROR.lock().unwrap().is_none());
+    }
+}
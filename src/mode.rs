@@ -0,0 +1,967 @@
+use chrono::{DateTime, Duration, Local, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+use clap::ValueEnum;
+
+use crate::time;
+
+/// Tracks running time for the stopwatch and countdown modes, excluding all
+/// the time spent while paused.
+#[derive(Debug, Clone)]
+pub struct Timer {
+    started: DateTime<Utc>,
+    paused_at: Option<DateTime<Utc>>,
+    paused_for: Duration,
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Timer {
+    pub fn new() -> Self {
+        Self {
+            started: time::now(),
+            paused_at: None,
+            paused_for: Duration::zero(),
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        let now = self.paused_at.unwrap_or_else(time::now);
+        now.signed_duration_since(self.started) - self.paused_for
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    pub fn pause(&mut self) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(time::now());
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if let Some(paused_at) = self.paused_at.take() {
+            self.paused_for += time::now().signed_duration_since(paused_at);
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        if self.is_paused() {
+            self.resume();
+        } else {
+            self.pause();
+        }
+    }
+
+    /// Restart the timer from zero. A paused timer stays paused.
+    pub fn reset(&mut self) {
+        let paused = self.is_paused();
+        *self = Self::new();
+        if paused {
+            self.pause();
+        }
+    }
+
+    // Continue with a new timer, which started once the given duration of
+    // this one elapsed
+    fn restart_after(&mut self, duration: Duration) {
+        self.started += self.paused_for + duration;
+        self.paused_for = Duration::zero();
+    }
+}
+
+/// The phases of a [`Pomodoro`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+/// Alternates between work phases and breaks. Every `cycles` work phases
+/// are followed by a long break instead of a short one.
+#[derive(Debug, Clone)]
+pub struct Pomodoro {
+    pub work: Duration,
+    pub short_break: Duration,
+    pub long_break: Duration,
+    pub cycles: u32,
+    phase: Phase,
+    completed: u32,
+    timer: Timer,
+}
+
+impl Pomodoro {
+    pub fn new(work: Duration, short_break: Duration, long_break: Duration, cycles: u32) -> Self {
+        Self {
+            work,
+            short_break,
+            long_break,
+            cycles: cycles.max(1),
+            phase: Phase::Work,
+            completed: 0,
+            timer: Timer::new(),
+        }
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// Number of work phases completed since the last long break
+    pub fn completed(&self) -> u32 {
+        self.completed
+    }
+
+    fn duration(&self) -> Duration {
+        match self.phase {
+            Phase::Work => self.work,
+            Phase::ShortBreak => self.short_break,
+            Phase::LongBreak => self.long_break,
+        }
+    }
+
+    fn remaining(&self) -> Duration {
+        self.duration() - self.timer.elapsed()
+    }
+
+    // Switch to the next phase once the current one is over. Returns the
+    // new phase.
+    fn advance(&mut self) -> Option<Phase> {
+        if self.remaining() > Duration::zero() {
+            return None;
+        }
+
+        self.timer.restart_after(self.duration());
+        self.phase = match self.phase {
+            Phase::Work => {
+                self.completed += 1;
+                if self.completed >= self.cycles {
+                    Phase::LongBreak
+                } else {
+                    Phase::ShortBreak
+                }
+            }
+            Phase::LongBreak => {
+                self.completed = 0;
+                Phase::Work
+            }
+            Phase::ShortBreak => Phase::Work,
+        };
+        Some(self.phase)
+    }
+
+    fn reset(&mut self) {
+        self.phase = Phase::Work;
+        self.completed = 0;
+        self.timer.reset();
+    }
+}
+
+/// A single part of an [`Interval`] timer
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    /// Displayed above the remaining time. Must only consist of characters
+    /// which have a [`Glyph`](crate::Glyph) and may be empty.
+    pub label: String,
+    pub duration: Duration,
+}
+
+impl Segment {
+    pub fn new<S: Into<String>>(label: S, duration: Duration) -> Self {
+        Self {
+            label: label.into(),
+            duration,
+        }
+    }
+}
+
+/// Counts down a sequence of segments one after the other. The whole
+/// sequence is repeated for the given number of rounds.
+#[derive(Debug, Clone)]
+pub struct Interval {
+    segments: Vec<Segment>,
+    rounds: u32,
+    round: u32,
+    index: usize,
+    timer: Timer,
+}
+
+impl Interval {
+    /// Panics if there are no segments
+    pub fn new(segments: Vec<Segment>, rounds: u32) -> Self {
+        assert!(
+            !segments.is_empty(),
+            "An interval needs at least one segment"
+        );
+        Self {
+            segments,
+            rounds: rounds.max(1),
+            round: 0,
+            index: 0,
+            timer: Timer::new(),
+        }
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// The segment currently counted down
+    pub fn segment(&self) -> &Segment {
+        &self.segments[self.index]
+    }
+
+    /// Index of the current segment
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The current round, starting at zero
+    pub fn round(&self) -> u32 {
+        self.round
+    }
+
+    fn remaining(&self) -> Duration {
+        self.segment().duration - self.timer.elapsed()
+    }
+
+    fn is_last_segment(&self) -> bool {
+        self.round + 1 == self.rounds && self.index + 1 == self.segments.len()
+    }
+
+    fn is_finished(&self) -> bool {
+        self.is_last_segment() && self.remaining() <= Duration::zero()
+    }
+
+    // Switch to the next segment (of the next round) once the current one
+    // is over. The last segment of the last round is never left.
+    fn advance(&mut self) -> bool {
+        if self.remaining() > Duration::zero() || self.is_last_segment() {
+            return false;
+        }
+
+        self.timer.restart_after(self.segment().duration);
+        self.index += 1;
+        if self.index == self.segments.len() {
+            self.index = 0;
+            self.round += 1;
+        }
+        true
+    }
+
+    fn reset(&mut self) {
+        self.round = 0;
+        self.index = 0;
+        self.timer.reset();
+    }
+}
+
+// Format a duration as HH:MM:SS. Durations of a day or longer are displayed
+// as DDdHH:MM instead, to keep the display at six digits. The `d` sets them
+// apart from hours, minutes and seconds.
+fn format_duration(duration: Duration) -> String {
+    let days = duration.num_days();
+    let hours = duration.num_hours() % 24;
+    let minutes = duration.num_minutes() % 60;
+    let seconds = duration.num_seconds() % 60;
+    if days > 0 {
+        format!("{:02}d{:02}:{:02}", days.min(99), hours, minutes)
+    } else {
+        format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+    }
+}
+
+/// Hours, minutes and seconds in 24-hour format
+pub const FORMAT_24H: &str = "%H:%M:%S";
+/// Hours and minutes in 24-hour format
+pub const FORMAT_24H_SHORT: &str = "%H:%M";
+/// Hours, minutes and seconds in 12-hour format followed by AM/PM
+pub const FORMAT_12H: &str = "%I:%M:%S %p";
+/// Hours and minutes in 12-hour format followed by AM/PM
+pub const FORMAT_12H_SHORT: &str = "%I:%M %p";
+/// Date as YYYY-MM-DD
+pub const FORMAT_DATE_YMD: &str = "%Y-%m-%d";
+/// Date as DD/MM/YYYY
+pub const FORMAT_DATE_DMY: &str = "%d/%m/%Y";
+/// Date as MM/DD/YYYY
+pub const FORMAT_DATE_MDY: &str = "%m/%d/%Y";
+
+/// Text displayed in front of the time of a [`Zone`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ZoneLabel {
+    None,
+    /// The current offset to UTC, eg. `+02` or `-0930`
+    Offset,
+    /// A fixed text, which must only consist of characters which have a
+    /// [`Glyph`](crate::Glyph)
+    Text(String),
+}
+
+/// A time zone shown by [`Mode::WorldClock`]
+#[derive(Debug, Clone)]
+pub struct Zone {
+    pub tz: Tz,
+    pub label: ZoneLabel,
+}
+
+impl Zone {
+    pub fn new(tz: Tz, label: ZoneLabel) -> Self {
+        Self { tz, label }
+    }
+
+    fn offset_label(&self, now: DateTime<Utc>) -> String {
+        let seconds = self
+            .tz
+            .offset_from_utc_datetime(&now.naive_utc())
+            .fix()
+            .local_minus_utc();
+        let sign = if seconds < 0 { '-' } else { '+' };
+        let (hours, minutes) = (seconds.abs() / 3600, seconds.abs() % 3600 / 60);
+        if minutes == 0 {
+            format!("{}{:02}", sign, hours)
+        } else {
+            format!("{}{:02}{:02}", sign, hours, minutes)
+        }
+    }
+
+    fn timestring(&self, format: &str, now: DateTime<Utc>) -> String {
+        let time = now.with_timezone(&self.tz).format(format).to_string();
+        match &self.label {
+            ZoneLabel::None => time,
+            ZoneLabel::Offset => format!("{} {}", self.offset_label(now), time),
+            ZoneLabel::Text(text) => format!("{} {}", text, time),
+        }
+    }
+}
+
+/// A lap recorded by the stopwatch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lap {
+    /// Starts at one for the first lap
+    pub number: usize,
+    /// Time since the previous lap
+    pub time: Duration,
+    /// Time since the start of the stopwatch
+    pub split: Duration,
+}
+
+/// Fractions of a second displayed by the [`Stopwatch`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+#[clap(rename_all = "lowercase")]
+pub enum Precision {
+    #[default]
+    #[value(help = "Whole seconds only")]
+    Seconds,
+    #[value(help = "Tenths of a second")]
+    Tenths,
+    #[value(help = "Hundredths of a second")]
+    Hundredths,
+}
+
+impl Precision {
+    // Number of fractional digits and the milliseconds of a single fraction
+    fn fraction(&self) -> Option<(usize, i64)> {
+        match self {
+            Self::Seconds => None,
+            Self::Tenths => Some((1, 100)),
+            Self::Hundredths => Some((2, 10)),
+        }
+    }
+}
+
+/// Measures the running time and records laps
+#[derive(Debug, Clone, Default)]
+pub struct Stopwatch {
+    timer: Timer,
+    splits: Vec<Duration>,
+    precision: Precision,
+}
+
+impl Stopwatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    pub fn precision(&self) -> Precision {
+        self.precision
+    }
+
+    /// Record a new lap ending now
+    pub fn record_lap(&mut self) -> Lap {
+        self.splits.push(self.timer.elapsed());
+        self.laps()[self.splits.len() - 1]
+    }
+
+    /// All recorded laps, starting with the first one
+    pub fn laps(&self) -> Vec<Lap> {
+        let mut previous = Duration::zero();
+        self.splits
+            .iter()
+            .enumerate()
+            .map(|(index, &split)| {
+                let lap = Lap {
+                    number: index + 1,
+                    time: split - previous,
+                    split,
+                };
+                previous = split;
+                lap
+            })
+            .collect()
+    }
+
+    // Counts the smallest fractions of a second displayed
+    fn current_fraction(&self) -> Option<i64> {
+        let (_, millis) = self.precision.fraction()?;
+        Some(self.timer.elapsed().num_milliseconds() / millis)
+    }
+
+    // The fraction is left out, once days are displayed instead of seconds
+    fn timestring(&self) -> String {
+        let elapsed = self.timer.elapsed();
+        let time = format_duration(elapsed);
+        match self.precision.fraction() {
+            Some((digits, millis)) if elapsed.num_days() == 0 => {
+                let fraction = elapsed.num_milliseconds() % 1000 / millis;
+                format!("{}.{:0digits$}", time, fraction, digits = digits)
+            }
+            _ => time,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.timer.reset();
+        self.splits.clear();
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Mode {
+    /// Show the current time using the given strftime pattern. The pattern
+    /// must only produce characters which have a [`Glyph`](crate::Glyph).
+    /// Every line of the pattern is displayed as a separate row (eg.
+    /// `"%H:%M\n%Y-%m-%d"` for the time above the date).
+    Clock(String),
+    /// Show the current time of every zone in a separate row, using the
+    /// given strftime pattern like [`Mode::Clock`]
+    WorldClock(String, Vec<Zone>),
+    Countdown(Duration, Timer),
+    Stopwatch(Stopwatch),
+    Pomodoro(Pomodoro),
+    Interval(Interval),
+    /// Show a fixed text, which must only contain characters with a
+    /// [`Glyph`](crate::Glyph). Every line is displayed as a separate row.
+    Text(String),
+}
+
+impl Default for Mode {
+    fn default() -> Self {
+        Self::Clock(FORMAT_24H.to_string())
+    }
+}
+
+impl Mode {
+    pub fn countdown(duration: Duration) -> Self {
+        Self::Countdown(duration, Timer::new())
+    }
+
+    pub fn stopwatch() -> Self {
+        Self::Stopwatch(Stopwatch::new())
+    }
+
+    fn timer_mut(&mut self) -> Option<&mut Timer> {
+        match self {
+            Self::Clock(_) | Self::WorldClock(..) | Self::Text(_) => None,
+            Self::Countdown(_, timer) => Some(timer),
+            Self::Stopwatch(stopwatch) => Some(&mut stopwatch.timer),
+            Self::Pomodoro(pomodoro) => Some(&mut pomodoro.timer),
+            Self::Interval(interval) => Some(&mut interval.timer),
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        match self {
+            Self::Clock(_) | Self::WorldClock(..) | Self::Text(_) => false,
+            Self::Countdown(_, timer) => timer.is_paused(),
+            Self::Stopwatch(stopwatch) => stopwatch.timer.is_paused(),
+            Self::Pomodoro(pomodoro) => pomodoro.timer.is_paused(),
+            Self::Interval(interval) => interval.timer.is_paused(),
+        }
+    }
+
+    /// Pause or resume a running timer. A clock or text can not be paused.
+    pub fn toggle_pause(&mut self) {
+        if let Some(timer) = self.timer_mut() {
+            timer.toggle_pause();
+        }
+    }
+
+    /// Reset a timer back to its initial value. Pomodoros and intervals
+    /// start over with their first phase or segment, the laps of a
+    /// stopwatch are discarded. A clock or text can not be reset.
+    pub fn reset(&mut self) {
+        match self {
+            Self::Stopwatch(stopwatch) => stopwatch.reset(),
+            Self::Pomodoro(pomodoro) => pomodoro.reset(),
+            Self::Interval(interval) => interval.reset(),
+            _ => {
+                if let Some(timer) = self.timer_mut() {
+                    timer.reset();
+                }
+            }
+        }
+    }
+
+    /// A countdown is finished once its full duration elapsed, an interval
+    /// once its last segment of the last round is over. Clocks, stopwatches,
+    /// pomodoros and texts never finish.
+    pub fn is_finished(&self) -> bool {
+        match self {
+            Self::Countdown(total, timer) => timer.elapsed() >= *total,
+            Self::Interval(interval) => interval.is_finished(),
+            Self::Clock(_)
+            | Self::WorldClock(..)
+            | Self::Stopwatch(..)
+            | Self::Pomodoro(_)
+            | Self::Text(_) => false,
+        }
+    }
+
+    /// Record a new lap of the stopwatch. Other modes have no laps.
+    pub fn record_lap(&mut self) -> Option<Lap> {
+        match self {
+            Self::Stopwatch(stopwatch) => Some(stopwatch.record_lap()),
+            _ => None,
+        }
+    }
+
+    /// All laps recorded by the stopwatch, starting with the first one
+    pub fn laps(&self) -> Vec<Lap> {
+        match self {
+            Self::Stopwatch(stopwatch) => stopwatch.laps(),
+            _ => vec![],
+        }
+    }
+
+    /// The current phase of a pomodoro
+    pub fn phase(&self) -> Option<Phase> {
+        match self {
+            Self::Pomodoro(pomodoro) => Some(pomodoro.phase()),
+            _ => None,
+        }
+    }
+
+    /// The index of the current interval segment
+    pub fn segment(&self) -> Option<usize> {
+        match self {
+            Self::Interval(interval) => Some(interval.index()),
+            _ => None,
+        }
+    }
+
+    /// Move a pomodoro or interval on to its next phase or segment once the
+    /// current one is over. Returns whether it moved on.
+    pub fn advance(&mut self) -> bool {
+        match self {
+            Self::Pomodoro(pomodoro) => pomodoro.advance().is_some(),
+            Self::Interval(interval) => interval.advance(),
+            _ => false,
+        }
+    }
+
+    /// A counter of whole seconds, which changes exactly when the seconds
+    /// displayed by `get_timestring` change. A text never changes.
+    pub fn current_second(&self) -> i64 {
+        match self {
+            Self::Clock(_) | Self::WorldClock(..) => time::now().timestamp(),
+            Self::Countdown(total, timer) => (*total - timer.elapsed()).num_seconds(),
+            Self::Stopwatch(stopwatch) => stopwatch.timer.elapsed().num_seconds(),
+            Self::Pomodoro(pomodoro) => pomodoro.remaining().num_seconds(),
+            Self::Interval(interval) => interval.remaining().num_seconds(),
+            Self::Text(_) => 0,
+        }
+    }
+
+    /// For a stopwatch showing fractions of a second, a counter of the
+    /// smallest fraction displayed by `get_timestring`
+    pub fn current_fraction(&self) -> Option<i64> {
+        match self {
+            Self::Stopwatch(stopwatch) => stopwatch.current_fraction(),
+            _ => None,
+        }
+    }
+
+    pub fn get_timestring(&self) -> String {
+        match self {
+            Self::Clock(format) => time::now().with_timezone(&Local).format(format).to_string(),
+            Self::WorldClock(format, zones) => {
+                let now = time::now();
+                zones
+                    .iter()
+                    .map(|zone| zone.timestring(format, now))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            Self::Countdown(total, timer) => {
+                let duration = *total - timer.elapsed();
+                // negative duration simply returns 00:00:00
+                if duration.num_seconds() < 0 {
+                    return "00:00:00".to_string();
+                }
+                format_duration(duration)
+            }
+            Self::Stopwatch(stopwatch) => stopwatch.timestring(),
+            Self::Pomodoro(pomodoro) => format_duration(pomodoro.remaining().max(Duration::zero())),
+            Self::Interval(interval) => {
+                let time = format_duration(interval.remaining().max(Duration::zero()));
+                match interval.segment().label.as_str() {
+                    "" => time,
+                    label => format!("{}\n{}", label, time),
+                }
+            }
+            Self::Text(text) => text.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn start() {
+        time::freeze(Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn durations_below_a_day_show_seconds() {
+        let duration = Duration::hours(1) + Duration::minutes(2) + Duration::seconds(3);
+        assert_eq!(format_duration(duration), "01:02:03");
+        assert_eq!(
+            format_duration(Duration::hours(23) + Duration::seconds(3599)),
+            "23:59:59"
+        );
+        assert_eq!(format_duration(Duration::zero()), "00:00:00");
+    }
+
+    #[test]
+    fn durations_of_days_show_days() {
+        let duration = Duration::days(1) + Duration::hours(2) + Duration::minutes(3);
+        assert_eq!(format_duration(duration), "01d02:03");
+        assert_eq!(format_duration(Duration::hours(72)), "03d00:00");
+    }
+
+    #[test]
+    fn days_are_clamped_at_99() {
+        let duration = Duration::days(150) + Duration::hours(5) + Duration::minutes(6);
+        assert_eq!(format_duration(duration), "99d05:06");
+    }
+
+    fn stopwatch_at(precision: Precision, millis: i64) -> String {
+        start();
+        let stopwatch = Stopwatch::new().with_precision(precision);
+        time::advance(Duration::milliseconds(millis));
+        stopwatch.timestring()
+    }
+
+    #[test]
+    fn stopwatch_shows_fractions_of_a_second() {
+        assert_eq!(stopwatch_at(Precision::Seconds, 1_500), "00:00:01");
+        assert_eq!(stopwatch_at(Precision::Tenths, 1_500), "00:00:01.5");
+        assert_eq!(stopwatch_at(Precision::Hundredths, 1_500), "00:00:01.50");
+        assert_eq!(stopwatch_at(Precision::Hundredths, 61_070), "00:01:01.07");
+    }
+
+    #[test]
+    fn fractions_of_a_second_are_truncated() {
+        assert_eq!(stopwatch_at(Precision::Seconds, 1_999), "00:00:01");
+        assert_eq!(stopwatch_at(Precision::Tenths, 1_999), "00:00:01.9");
+        assert_eq!(stopwatch_at(Precision::Hundredths, 1_999), "00:00:01.99");
+    }
+
+    #[test]
+    fn fractions_are_left_out_once_days_are_shown() {
+        let millis = Duration::days(1).num_milliseconds() + 1_500;
+        assert_eq!(stopwatch_at(Precision::Hundredths, millis), "01d00:00");
+    }
+
+    #[test]
+    fn timer_counts_frozen_time() {
+        start();
+        let timer = Timer::new();
+        assert_eq!(timer.elapsed(), Duration::zero());
+        time::advance(Duration::seconds(5));
+        assert_eq!(timer.elapsed(), Duration::seconds(5));
+    }
+
+    #[test]
+    fn timer_excludes_paused_time() {
+        start();
+        let mut timer = Timer::new();
+        time::advance(Duration::seconds(5));
+        timer.pause();
+        time::advance(Duration::seconds(3));
+        assert!(timer.is_paused());
+        assert_eq!(timer.elapsed(), Duration::seconds(5));
+        timer.resume();
+        time::advance(Duration::seconds(2));
+        assert_eq!(timer.elapsed(), Duration::seconds(7));
+
+        // Pausing twice keeps the first pause
+        timer.pause();
+        time::advance(Duration::seconds(1));
+        timer.pause();
+        time::advance(Duration::seconds(1));
+        timer.toggle_pause();
+        assert!(!timer.is_paused());
+        assert_eq!(timer.elapsed(), Duration::seconds(7));
+    }
+
+    #[test]
+    fn reset_timer_stays_paused() {
+        start();
+        let mut timer = Timer::new();
+        time::advance(Duration::seconds(5));
+        timer.reset();
+        assert_eq!(timer.elapsed(), Duration::zero());
+        assert!(!timer.is_paused());
+
+        timer.pause();
+        timer.reset();
+        time::advance(Duration::seconds(5));
+        assert!(timer.is_paused());
+        assert_eq!(timer.elapsed(), Duration::zero());
+    }
+
+    #[test]
+    fn restarted_timer_keeps_overshoot() {
+        start();
+        let mut timer = Timer::new();
+        timer.pause();
+        time::advance(Duration::seconds(4));
+        timer.resume();
+        time::advance(Duration::seconds(12));
+        timer.restart_after(Duration::seconds(10));
+        assert_eq!(timer.elapsed(), Duration::seconds(2));
+    }
+
+    fn pomodoro() -> Pomodoro {
+        Pomodoro::new(
+            Duration::minutes(25),
+            Duration::minutes(5),
+            Duration::minutes(15),
+            2,
+        )
+    }
+
+    #[test]
+    fn pomodoro_alternates_work_and_breaks() {
+        start();
+        let mut pomodoro = pomodoro();
+        time::advance(Duration::minutes(25) - Duration::seconds(1));
+        assert_eq!(pomodoro.advance(), None);
+        assert_eq!(pomodoro.remaining(), Duration::seconds(1));
+
+        time::advance(Duration::seconds(1));
+        assert_eq!(pomodoro.advance(), Some(Phase::ShortBreak));
+        assert_eq!(pomodoro.completed(), 1);
+        assert_eq!(pomodoro.remaining(), Duration::minutes(5));
+        assert_eq!(pomodoro.advance(), None);
+
+        time::advance(Duration::minutes(5));
+        assert_eq!(pomodoro.advance(), Some(Phase::Work));
+        assert_eq!(pomodoro.completed(), 1);
+    }
+
+    #[test]
+    fn pomodoro_takes_long_break_after_cycles() {
+        start();
+        let mut pomodoro = pomodoro();
+        for phase in [Phase::ShortBreak, Phase::Work, Phase::LongBreak] {
+            time::advance(pomodoro.remaining());
+            assert_eq!(pomodoro.advance(), Some(phase));
+        }
+        assert_eq!(pomodoro.completed(), 2);
+        assert_eq!(pomodoro.remaining(), Duration::minutes(15));
+
+        // The cycle starts over after the long break
+        time::advance(Duration::minutes(15));
+        assert_eq!(pomodoro.advance(), Some(Phase::Work));
+        assert_eq!(pomodoro.completed(), 0);
+        time::advance(Duration::minutes(25));
+        assert_eq!(pomodoro.advance(), Some(Phase::ShortBreak));
+    }
+
+    #[test]
+    fn pomodoro_phase_keeps_overshoot() {
+        start();
+        let mut pomodoro = pomodoro();
+        time::advance(Duration::minutes(25) + Duration::seconds(3));
+        assert_eq!(pomodoro.advance(), Some(Phase::ShortBreak));
+        assert_eq!(
+            pomodoro.remaining(),
+            Duration::minutes(5) - Duration::seconds(3)
+        );
+    }
+
+    #[test]
+    fn reset_pomodoro_starts_with_work() {
+        start();
+        let mut pomodoro = pomodoro();
+        time::advance(Duration::minutes(25));
+        pomodoro.advance();
+        pomodoro.reset();
+        assert_eq!(pomodoro.phase(), Phase::Work);
+        assert_eq!(pomodoro.completed(), 0);
+        assert_eq!(pomodoro.remaining(), Duration::minutes(25));
+    }
+
+    fn interval(rounds: u32) -> Interval {
+        Interval::new(
+            vec![
+                Segment::new("ON", Duration::seconds(10)),
+                Segment::new("OFF", Duration::seconds(5)),
+            ],
+            rounds,
+        )
+    }
+
+    #[test]
+    fn interval_moves_through_segments_and_rounds() {
+        start();
+        let mut interval = interval(2);
+        time::advance(Duration::seconds(9));
+        assert!(!interval.advance());
+
+        let steps = [(1, 0, 1), (5, 1, 0), (10, 1, 1)];
+        for (seconds, round, index) in steps {
+            time::advance(Duration::seconds(seconds));
+            assert!(interval.advance());
+            assert_eq!((interval.round(), interval.index()), (round, index));
+        }
+        assert!(!interval.is_finished());
+    }
+
+    #[test]
+    fn interval_stays_in_last_segment() {
+        start();
+        let mut interval = interval(1);
+        time::advance(Duration::seconds(10));
+        assert!(interval.advance());
+        assert!(interval.is_last_segment());
+
+        time::advance(Duration::seconds(6));
+        assert!(!interval.advance());
+        assert!(interval.is_finished());
+        assert_eq!(interval.index(), 1);
+        assert_eq!(interval.remaining(), -Duration::seconds(1));
+    }
+
+    #[test]
+    fn single_segment_wraps_around() {
+        start();
+        let mut interval = Interval::new(vec![Segment::new("", Duration::seconds(10))], 3);
+        for round in 1..3 {
+            time::advance(Duration::seconds(10));
+            assert!(interval.advance());
+            assert_eq!((interval.round(), interval.index()), (round, 0));
+        }
+        time::advance(Duration::seconds(10));
+        assert!(!interval.advance());
+        assert!(interval.is_finished());
+    }
+
+    fn lap(number: usize, time: i64, split: i64) -> Lap {
+        Lap {
+            number,
+            time: Duration::milliseconds(time),
+            split: Duration::milliseconds(split),
+        }
+    }
+
+    #[test]
+    fn stopwatch_records_lap_and_split_times() {
+        start();
+        let mut stopwatch = Stopwatch::new();
+        time::advance(Duration::milliseconds(65_250));
+        assert_eq!(stopwatch.record_lap(), lap(1, 65_250, 65_250));
+        time::advance(Duration::seconds(30));
+        assert_eq!(stopwatch.record_lap(), lap(2, 30_000, 95_250));
+        assert_eq!(
+            stopwatch.laps(),
+            vec![lap(1, 65_250, 65_250), lap(2, 30_000, 95_250)]
+        );
+    }
+
+    #[test]
+    fn laps_recorded_while_paused_exclude_the_pause() {
+        start();
+        let mut mode = Mode::stopwatch();
+        time::advance(Duration::seconds(10));
+        mode.toggle_pause();
+        time::advance(Duration::seconds(20));
+        assert_eq!(mode.record_lap(), Some(lap(1, 10_000, 10_000)));
+        assert_eq!(mode.record_lap(), Some(lap(2, 0, 10_000)));
+        mode.toggle_pause();
+        time::advance(Duration::seconds(5));
+        assert_eq!(mode.record_lap(), Some(lap(3, 5_000, 15_000)));
+    }
+
+    #[test]
+    fn reset_discards_the_laps() {
+        start();
+        let mut mode = Mode::stopwatch();
+        time::advance(Duration::seconds(10));
+        mode.record_lap();
+        mode.reset();
+        assert_eq!(mode.laps(), vec![]);
+        time::advance(Duration::seconds(7));
+        assert_eq!(mode.record_lap(), Some(lap(1, 7_000, 7_000)));
+        assert_eq!(Mode::Text("HI".to_string()).record_lap(), None);
+    }
+
+    fn offset(tz: Tz, month: u32) -> String {
+        time::freeze(Utc.with_ymd_and_hms(2024, month, 15, 12, 0, 0).unwrap());
+        Zone::new(tz, ZoneLabel::Offset).offset_label(time::now())
+    }
+
+    #[test]
+    fn offset_labels_follow_the_zone() {
+        assert_eq!(offset(Tz::Europe__Berlin, 1), "+01");
+        assert_eq!(offset(Tz::Europe__Berlin, 7), "+02");
+        assert_eq!(offset(Tz::America__New_York, 1), "-05");
+        assert_eq!(offset(Tz::UTC, 1), "+00");
+    }
+
+    #[test]
+    fn offset_labels_include_minutes_when_needed() {
+        assert_eq!(offset(Tz::Asia__Kolkata, 1), "+0530");
+        assert_eq!(offset(Tz::Asia__Kathmandu, 7), "+0545");
+        assert_eq!(offset(Tz::America__St_Johns, 1), "-0330");
+    }
+
+    #[test]
+    fn zones_are_labeled_in_front_of_the_time() {
+        time::freeze(Utc.with_ymd_and_hms(2024, 7, 15, 12, 34, 0).unwrap());
+        let now = time::now();
+        let zone = |label| Zone::new(Tz::Asia__Kolkata, label);
+        assert_eq!(zone(ZoneLabel::None).timestring("%H:%M", now), "18:04");
+        assert_eq!(
+            zone(ZoneLabel::Offset).timestring("%H:%M", now),
+            "+0530 18:04"
+        );
+        assert_eq!(
+            zone(ZoneLabel::Text("IN".to_string())).timestring("%H:%M", now),
+            "IN 18:04"
+        );
+    }
+}
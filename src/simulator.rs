@@ -0,0 +1,187 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fmt::Write;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Error, Result};
+use pixel_loop::canvas::{Canvas, InMemoryCanvas};
+use pixel_loop::color::Color;
+
+use crate::font::Font;
+use crate::glyph::Glyph;
+use crate::layout::GLYPH_HEIGHT;
+use crate::tetromino::{Colorscheme, DigitBoard};
+
+/// The cells covered by a glyph. As ASCII art every row is written as a
+/// line, using `#` for covered and `.` for empty cells. The upper half of the
+/// digit 0 for example looks like this:
+///
+/// ```text
+/// ######
+/// ######
+/// ##..##
+/// ##..##
+/// ##..##
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bitmap {
+    rows: Vec<Vec<bool>>,
+}
+
+impl Bitmap {
+    fn from_cells(cells: &HashSet<(i64, i64)>, x_range: (i64, i64), y_range: (i64, i64)) -> Self {
+        Self {
+            rows: (y_range.0..y_range.1)
+                .map(|y| {
+                    (x_range.0..x_range.1)
+                        .map(|x| cells.contains(&(x, y)))
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.rows.first().map_or(0, Vec::len)
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Whether the cell in column `x` of row `y` (counted from the top) is
+    /// covered
+    pub fn is_set(&self, x: usize, y: usize) -> bool {
+        self.rows
+            .get(y)
+            .and_then(|row| row.get(x))
+            .copied()
+            .unwrap_or(false)
+    }
+}
+
+// Empty lines as well as the indentation of the lines are ignored
+impl FromStr for Bitmap {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let rows = s
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                line.chars()
+                    .map(|c| match c {
+                        '#' => Ok(true),
+                        '.' => Ok(false),
+                        _ => bail!("Invalid cell '{}' in bitmap. Expected '#' or '.'", c),
+                    })
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if rows.is_empty() {
+            bail!("The bitmap has no rows");
+        }
+        if rows.iter().any(|row| row.len() != rows[0].len()) {
+            bail!("All rows of the bitmap must have the same width");
+        }
+        Ok(Self { rows })
+    }
+}
+
+impl fmt::Display for Bitmap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (y, row) in self.rows.iter().enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+            for &set in row {
+                write!(f, "{}", if set { '#' } else { '.' })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// The glyph is simulated at the default frame rate. Even the slowest glyph
+// settles within a fraction of the timeout.
+const FRAME_TIME: Duration = Duration::from_nanos(16_666_667);
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Drop the tetrominos of a glyph on an in-memory board, just like a
+/// [`Clock`](crate::Clock) does, and return the cells covered once all of
+/// them came to a hold.
+///
+/// The bitmap spans the width of the glyph and `GLYPH_HEIGHT` rows. Cells
+/// outside of that area (eg. of a tetromino sticking out of the glyph) widen
+/// the bitmap accordingly.
+pub fn settle(font: &Arc<Font>, glyph: Glyph) -> Bitmap {
+    // Leave enough room above the glyph for tetrominos piling up too high
+    let y_stop = 3 * GLYPH_HEIGHT;
+    let canvas = InMemoryCanvas::new(
+        glyph.width() as u32,
+        y_stop as u32,
+        &Color::from_rgb(0, 0, 0),
+    );
+    let mut board =
+        DigitBoard::new(0, 0, y_stop, Colorscheme::default(), glyph).with_font(Arc::clone(font));
+    let mut elapsed = Duration::ZERO;
+    while !board.is_settled() && elapsed < TIMEOUT {
+        board.update(&canvas, FRAME_TIME);
+        elapsed += FRAME_TIME;
+    }
+
+    let cells = board.board().occupied_cells();
+    let x_min = cells.iter().map(|&(x, _)| x).min().unwrap_or(0).min(0);
+    let x_max = cells.iter().map(|&(x, _)| x + 1).max().unwrap_or(0);
+    let y_min = cells.iter().map(|&(_, y)| y).min().unwrap_or(y_stop);
+    Bitmap::from_cells(
+        &cells,
+        (x_min, x_max.max(glyph.width())),
+        (y_min.min(y_stop - GLYPH_HEIGHT), y_stop),
+    )
+}
+
+// Symbols of the colors in a snapshot. Any further colors share `*`.
+const SNAPSHOT_SYMBOLS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+/// Describe the pixels of a canvas as text, to compare rendered frames
+/// against stored snapshots. Every color gets a symbol in the order of
+/// appearance, listed in a legend above the rows of pixels. Black pixels are
+/// written as `.`.
+pub fn snapshot<C: Canvas>(canvas: &C) -> String {
+    let black = Color::from_rgb(0, 0, 0);
+    let mut colors: Vec<Color> = Vec::new();
+    let mut rows = String::new();
+    for y in 0..canvas.height() {
+        for x in 0..canvas.width() {
+            let color = *canvas.get(x, y);
+            if color == black {
+                rows.push('.');
+                continue;
+            }
+            let index = match colors.iter().position(|&known| known == color) {
+                Some(index) => index,
+                None => {
+                    colors.push(color);
+                    colors.len() - 1
+                }
+            };
+            rows.push(SNAPSHOT_SYMBOLS.chars().nth(index).unwrap_or('*'));
+        }
+        rows.push('\n');
+    }
+
+    let mut legend = String::new();
+    for (index, color) in colors.iter().enumerate() {
+        let symbol = SNAPSHOT_SYMBOLS.chars().nth(index).unwrap_or('*');
+        let _ = writeln!(
+            legend,
+            "{} #{:02x}{:02x}{:02x}",
+            symbol, color.r, color.g, color.b
+        );
+    }
+    format!("{}\n{}", legend, rows)
+}
@@ -0,0 +1,188 @@
+use std::collections::HashSet;
+
+use crate::digits::{Animation, FallingTetromino};
+use crate::simulator::Bitmap;
+use crate::tetromino::{Rotation, Shape};
+
+const SHAPES: [Shape; 7] = [
+    Shape::L,
+    Shape::J,
+    Shape::O,
+    Shape::T,
+    Shape::I,
+    Shape::S,
+    Shape::Z,
+];
+
+const ROTATIONS: [Rotation; 4] = [
+    Rotation::NoRotation,
+    Rotation::Degrees90,
+    Rotation::Degrees180,
+    Rotation::Degrees270,
+];
+
+// The cells of a bitmap, indexed by `y * width + x`
+type Cells = Vec<bool>;
+
+// A shape in one rotation along with the cells it covers
+type Candidate = (Shape, Rotation, [(i64, i64); 4]);
+
+struct Solver {
+    width: i64,
+    height: i64,
+    target: Cells,
+    // Every shape in every rotation covering a distinct set of cells
+    tetrominos: Vec<Candidate>,
+    // Partial fillings already known to lead nowhere
+    dead_ends: HashSet<Cells>,
+}
+
+/// Search for a sequence of tetrominos which, dropped one after the other
+/// like on a [`DigitBoard`](crate::DigitBoard), build the given bitmap.
+///
+/// Every tetromino falls straight down from above the bitmap until it hits
+/// the bottom or a previously dropped one. `None` is returned if no such
+/// sequence exists, eg. because the number of cells is not a multiple of
+/// four or a cell would need to be filled below an overhang.
+pub fn solve(target: &Bitmap) -> Option<Animation> {
+    let (width, height) = (target.width(), target.height());
+    let target: Cells = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| target.is_set(x, y))
+        .collect();
+    if target.iter().filter(|&&set| set).count() % 4 != 0 {
+        return None;
+    }
+
+    let mut tetrominos: Vec<Candidate> = Vec::new();
+    for shape in SHAPES {
+        for rotation in ROTATIONS {
+            let mut cells = shape.cells(rotation);
+            cells.sort_unstable();
+            if !tetrominos.iter().any(|&(_, _, other)| other == cells) {
+                tetrominos.push((shape, rotation, cells));
+            }
+        }
+    }
+
+    let mut solver = Solver {
+        width: width as i64,
+        height: height as i64,
+        target,
+        tetrominos,
+        dead_ends: HashSet::new(),
+    };
+    let empty = vec![false; width * height];
+    let mut animation = Vec::new();
+    solver.search(&empty, &mut animation).then_some(animation)
+}
+
+impl Solver {
+    fn index(&self, x: i64, y: i64) -> usize {
+        (y * self.width + x) as usize
+    }
+
+    fn search(&mut self, filled: &Cells, animation: &mut Animation) -> bool {
+        if *filled == self.target {
+            return true;
+        }
+        if self.dead_ends.contains(filled) {
+            return false;
+        }
+        for n in 0..self.tetrominos.len() {
+            let (shape, rotation, cells) = self.tetrominos[n];
+            let x_min = cells.iter().map(|&(cx, _)| cx).min().unwrap_or(0);
+            let x_max = cells.iter().map(|&(cx, _)| cx).max().unwrap_or(0);
+            for dx in -x_min..self.width - x_max {
+                let Some(next) = self.drop(filled, &cells, dx) else {
+                    continue;
+                };
+                if !self.is_completable(&next) {
+                    continue;
+                }
+                animation.push(FallingTetromino::new(shape, rotation, dx));
+                if self.search(&next, animation) {
+                    return true;
+                }
+                animation.pop();
+            }
+        }
+        self.dead_ends.insert(filled.clone());
+        false
+    }
+
+    // Let the tetromino fall down in column `dx` until it hits the bottom or
+    // a filled cell. `None` if it comes to a hold on cells not part of the
+    // target.
+    fn drop(&self, filled: &Cells, cells: &[(i64, i64); 4], dx: i64) -> Option<Cells> {
+        let hits = |y: i64| {
+            cells.iter().any(|&(cx, cy)| {
+                let below = y + cy + 1;
+                below >= self.height || (below >= 0 && filled[self.index(dx + cx, below)])
+            })
+        };
+        let mut y = 0;
+        while !hits(y) {
+            y += 1;
+        }
+
+        let mut next = filled.clone();
+        for &(cx, cy) in cells {
+            if y + cy < 0 {
+                return None;
+            }
+            let index = self.index(dx + cx, y + cy);
+            if !self.target[index] {
+                return None;
+            }
+            next[index] = true;
+        }
+        Some(next)
+    }
+
+    // Tetrominos can only fall straight down, so target cells below a filled
+    // one can never be reached. Every area of connected cells still to be
+    // filled needs to be built from whole tetrominos.
+    fn is_completable(&self, filled: &Cells) -> bool {
+        let mut remaining: Cells = self
+            .target
+            .iter()
+            .zip(filled)
+            .map(|(&target, &filled)| target && !filled)
+            .collect();
+        for x in 0..self.width {
+            let top = (0..self.height).find(|&y| filled[self.index(x, y)]);
+            if let Some(top) = top {
+                if (top..self.height).any(|y| remaining[self.index(x, y)]) {
+                    return false;
+                }
+            }
+        }
+
+        for start in 0..remaining.len() {
+            if !remaining[start] {
+                continue;
+            }
+            remaining[start] = false;
+            let mut stack = vec![start as i64];
+            let mut size = 0;
+            while let Some(index) = stack.pop() {
+                size += 1;
+                let (x, y) = (index % self.width, index / self.width);
+                for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+                    if (0..self.width).contains(&nx) && (0..self.height).contains(&ny) {
+                        let neighbour = self.index(nx, ny);
+                        if remaining[neighbour] {
+                            remaining[neighbour] = false;
+                            stack.push(neighbour as i64);
+                        }
+                    }
+                }
+            }
+            if size % 4 != 0 {
+                return false;
+            }
+        }
+        true
+    }
+}
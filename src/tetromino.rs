@@ -1,12 +1,19 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
 use clap::ValueEnum;
 use pixel_loop::canvas::Canvas;
 use pixel_loop::color::Color;
 
 use crate::digits::{ Animation, Digit, FallingTetromino};
+use crate::font::Font;
+use crate::glyph::Glyph;
 
-#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, ValueEnum)]
 #[clap(rename_all = "lowercase")]
 pub enum Colorscheme {
+    #[default]
     #[value(help = "Classic Tetris colors with bright, distinct shades")]
     Original,
     #[value(help = "Different shades of gray for a monochrome look")]
@@ -33,14 +40,8 @@ pub enum Colorscheme {
     Purple,
 }
 
-impl Default for Colorscheme {
-    fn default() -> Self {
-        Self::Original
-    }
-}
-
 impl Colorscheme {
-    pub fn apply(&self, shape: Shape, digit: Digit, position: usize) -> Color {
+    pub fn apply(&self, shape: Shape, glyph: Glyph, position: usize) -> Color {
         match self {
             Colorscheme::Original => match shape {
                 Shape::L => Color::from_rgb(223, 155, 42),
@@ -60,27 +61,38 @@ impl Colorscheme {
                 Shape::S => Color::from_rgb(200, 200, 200),
                 Shape::Z => Color::from_rgb(120, 120, 120),
             },
-            Colorscheme::Position => match position {
+            Colorscheme::Position => match position % 7 {
                 0 => Color::from_rgb(237, 238, 57),
                 1 => Color::from_rgb(213, 50, 27),
                 2 => Color::from_rgb(223, 155, 42),
                 3 => Color::from_rgb(136, 26, 231),
                 4 => Color::from_rgb(103, 232, 236),
                 5 => Color::from_rgb(100, 233, 49),
-                6 => Color::from_rgb(213, 50, 27),
-                _ => panic!("Unknown position for Colorscheme 'position' {}", position),
+                _ => Color::from_rgb(213, 50, 27),
             },
-            Colorscheme::Digit => match digit {
-                Digit::Zero => Color::from_rgb(180, 180, 180),
-                Digit::One => Color::from_rgb(255, 215, 0),
-                Digit::Two => Color::from_rgb(255, 105, 180),
-                Digit::Three => Color::from_rgb(0, 139, 139),
-                Digit::Four => Color::from_rgb(255, 160, 122),
-                Digit::Five => Color::from_rgb(147, 112, 219),
-                Digit::Six => Color::from_rgb(32, 178, 170),
-                Digit::Seven => Color::from_rgb(255, 218, 185),
-                Digit::Eight => Color::from_rgb(176, 196, 222),
-                Digit::Nine => Color::from_rgb(255, 192, 203),
+            Colorscheme::Digit => match glyph {
+                // Separators and letters share the color of the zero
+                Glyph::Colon
+                | Glyph::Dot
+                | Glyph::Comma
+                | Glyph::Apostrophe
+                | Glyph::Exclamation
+                | Glyph::Question
+                | Glyph::Dash
+                | Glyph::Slash
+                | Glyph::Plus
+                | Glyph::Space
+                | Glyph::Letter(_)
+                | Glyph::Digit(Digit::Zero) => Color::from_rgb(180, 180, 180),
+                Glyph::Digit(Digit::One) => Color::from_rgb(255, 215, 0),
+                Glyph::Digit(Digit::Two) => Color::from_rgb(255, 105, 180),
+                Glyph::Digit(Digit::Three) => Color::from_rgb(0, 139, 139),
+                Glyph::Digit(Digit::Four) => Color::from_rgb(255, 160, 122),
+                Glyph::Digit(Digit::Five) => Color::from_rgb(147, 112, 219),
+                Glyph::Digit(Digit::Six) => Color::from_rgb(32, 178, 170),
+                Glyph::Digit(Digit::Seven) => Color::from_rgb(255, 218, 185),
+                Glyph::Digit(Digit::Eight) => Color::from_rgb(176, 196, 222),
+                Glyph::Digit(Digit::Nine) => Color::from_rgb(255, 192, 203),
             },
             Colorscheme::Neon => match shape {
                 // Bright, glowing neon colors
@@ -166,7 +178,7 @@ impl Colorscheme {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Shape {
     L,
     J,
@@ -191,7 +203,8 @@ pub enum Shape {
 //     }
 // }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[allow(clippy::enum_variant_names)]
 pub enum Rotation {
     Degrees90,
     Degrees180,
@@ -199,6 +212,37 @@ pub enum Rotation {
     NoRotation,
 }
 
+impl Shape {
+    // The cells covered by the shape relative to the coordinate of its
+    // tetromino (see `Tetromino` for the coordinate system). A cell `(dx, dy)`
+    // covers the row `y + dy`, therefore all `dy` are negative.
+    pub fn cells(&self, rotation: Rotation) -> [(i64, i64); 4] {
+        use Rotation::*;
+        use Shape::*;
+        match (self, rotation) {
+            (L, NoRotation) => [(0, -2), (0, -1), (1, -2), (2, -2)],
+            (L, Degrees90) => [(0, -3), (0, -2), (0, -1), (-1, -3)],
+            (L, Degrees180) => [(0, -1), (1, -1), (2, -1), (2, -2)],
+            (L, Degrees270) => [(0, -3), (0, -2), (0, -1), (1, -1)],
+            (J, NoRotation) => [(-2, -2), (-1, -2), (0, -2), (0, -1)],
+            (J, Degrees90) => [(0, -1), (1, -1), (1, -3), (1, -2)],
+            (J, Degrees180) => [(0, -2), (0, -1), (1, -1), (2, -1)],
+            (J, Degrees270) => [(0, -3), (0, -2), (0, -1), (1, -3)],
+            (O, _) => [(0, -2), (1, -2), (0, -1), (1, -1)],
+            (T, NoRotation) => [(-1, -2), (0, -2), (1, -2), (0, -1)],
+            (T, Degrees90) => [(0, -3), (0, -2), (0, -1), (-1, -2)],
+            (T, Degrees180) => [(0, -1), (1, -1), (2, -1), (1, -2)],
+            (T, Degrees270) => [(0, -3), (0, -2), (0, -1), (1, -2)],
+            (I, NoRotation) | (I, Degrees180) => [(0, -1), (1, -1), (2, -1), (3, -1)],
+            (I, Degrees90) | (I, Degrees270) => [(0, -4), (0, -3), (0, -2), (0, -1)],
+            (S, NoRotation) | (S, Degrees180) => [(0, -1), (1, -1), (1, -2), (2, -2)],
+            (S, Degrees90) | (S, Degrees270) => [(0, -2), (0, -1), (-1, -3), (-1, -2)],
+            (Z, NoRotation) | (Z, Degrees180) => [(0, -1), (1, -1), (-1, -2), (0, -2)],
+            (Z, Degrees90) | (Z, Degrees270) => [(0, -2), (0, -1), (1, -3), (1, -2)],
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 enum FallState {
     In,
@@ -222,6 +266,7 @@ enum FallState {
 // this is not important to us. Rotationonal symetry is not a requirement for
 // the clock.  The shapes are based upon this reference:
 // https://tetris.wiki/images/b/b5/Tgm_basic_ars_description.png
+#[derive(Clone, Copy)]
 struct Tetromino {
     shape: Shape,
     rotation: Rotation,
@@ -229,127 +274,220 @@ struct Tetromino {
     y: i64,
     color: Color,
     fall: FallState,
+    // Cells per second and the fraction of a cell moved, but not yet taken
+    // into account by `y`
+    velocity: f64,
+    progress: f64,
 }
 
+impl Tetromino {
+    fn cells(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        self.shape
+            .cells(self.rotation)
+            .into_iter()
+            .map(|(dx, dy)| (self.x + dx, self.y + dy))
+    }
+}
 
 
-fn would_tetromino_collide_with_canvas<C: Canvas>(
-    Tetromino {
-        shape,
-        rotation,
-        x,
-        y,
-        ..
-    }: &Tetromino,
-    canvas: &C,
-) -> bool {
-    let empty = Color::from_rgb(0, 0, 0);
-    use Rotation::*;
-    use Shape::*;
-    match (shape, rotation) {
-        (L, NoRotation) => {
-            !canvas.is_empty_or_color(*x, *y, &empty)
-                || !canvas.is_empty_or_color(*x + 1, *y - 1, &empty)
-                || !canvas.is_empty_or_color(*x + 2, *y - 1, &empty)
-        }
-        (L, Degrees90) => {
-            !canvas.is_empty_or_color(*x, *y, &empty)
-                || !canvas.is_empty_or_color(*x - 1, *y - 2, &empty)
-        }
-        (L, Degrees180) => {
-            !canvas.is_empty_or_color(*x, *y, &empty)
-                || !canvas.is_empty_or_color(*x + 1, *y, &empty)
-                || !canvas.is_empty_or_color(*x + 2, *y, &empty)
-        }
-        (L, Degrees270) => {
-            !canvas.is_empty_or_color(*x, *y, &empty)
-                || !canvas.is_empty_or_color(*x + 1, *y, &empty)
-        }
-        (J, NoRotation) => {
-            !canvas.is_empty_or_color(*x, *y, &empty)
-                || !canvas.is_empty_or_color(*x - 1, *y - 1, &empty)
-                || !canvas.is_empty_or_color(*x - 2, *y - 1, &empty)
-        }
-        (J, Degrees90) => {
-            !canvas.is_empty_or_color(*x, *y, &empty)
-                || !canvas.is_empty_or_color(*x + 1, *y, &empty)
-        }
-        (J, Degrees180) => {
-            !canvas.is_empty_or_color(*x, *y, &empty)
-                || !canvas.is_empty_or_color(*x + 1, *y, &empty)
-                || !canvas.is_empty_or_color(*x + 2, *y, &empty)
-        }
-        (J, Degrees270) => {
-            !canvas.is_empty_or_color(*x, *y, &empty)
-                || !canvas.is_empty_or_color(*x + 1, *y - 2, &empty)
-        }
-        (O, _) => {
-            !canvas.is_empty_or_color(*x, *y, &empty)
-                || !canvas.is_empty_or_color(*x + 1, *y, &empty)
-        }
-        (T, NoRotation) => {
-            !canvas.is_empty_or_color(*x, *y, &empty)
-                || !canvas.is_empty_or_color(*x + 1, *y - 1, &empty)
-                || !canvas.is_empty_or_color(*x - 1, *y - 1, &empty)
-        }
-        (T, Degrees90) => {
-            !canvas.is_empty_or_color(*x, *y, &empty)
-                || !canvas.is_empty_or_color(*x - 1, *y - 1, &empty)
-        }
-        (T, Degrees180) => {
-            !canvas.is_empty_or_color(*x, *y, &empty)
-                || !canvas.is_empty_or_color(*x + 1, *y, &empty)
-                || !canvas.is_empty_or_color(*x + 2, *y, &empty)
-        }
-        (T, Degrees270) => {
-            !canvas.is_empty_or_color(*x, *y, &empty)
-                || !canvas.is_empty_or_color(*x + 1, *y - 1, &empty)
-        }
-        (I, NoRotation) | (I, Degrees180) => {
-            !canvas.is_empty_or_color(*x, *y, &empty)
-                || !canvas.is_empty_or_color(*x + 1, *y, &empty)
-                || !canvas.is_empty_or_color(*x + 2, *y, &empty)
-                || !canvas.is_empty_or_color(*x + 3, *y, &empty)
-        }
-        (I, Degrees90) | (I, Degrees270) => !canvas.is_empty_or_color(*x, *y, &empty),
-        (S, NoRotation) | (S, Degrees180) => {
-            !canvas.is_empty_or_color(*x, *y, &empty)
-                || !canvas.is_empty_or_color(*x + 1, *y, &empty)
-                || !canvas.is_empty_or_color(*x + 2, *y - 1, &empty)
-        }
-        (S, Degrees90) | (S, Degrees270) => {
-            !canvas.is_empty_or_color(*x, *y, &empty)
-                || !canvas.is_empty_or_color(*x - 1, *y - 1, &empty)
-        }
-        (Z, NoRotation) | (Z, Degrees180) => {
-            !canvas.is_empty_or_color(*x, *y, &empty)
-                || !canvas.is_empty_or_color(*x + 1, *y, &empty)
-                || !canvas.is_empty_or_color(*x - 1, *y - 1, &empty)
+
+fn dim_color(color: &Color) -> Color {
+    Color::from_rgb(color.r / 3, color.g / 3, color.b / 3)
+}
+
+// Time a flashing board stays in either of its two states
+const FLASH_INTERVAL: Duration = Duration::from_millis(250);
+
+// Frame times are rarely exact (eg. 1/60s in nanoseconds), which must not
+// cost a tetromino the cell it should have reached
+const CELL_EPSILON: f64 = 1e-6;
+
+/// Settings controlling the movement of the tetrominos on a [`Board`] and
+/// the timing of a [`DigitBoard`]. All of them are given per second, so the
+/// animation looks the same at any frame rate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Physics {
+    /// Cells a tetromino falls per second
+    pub fall_speed: f64,
+    /// Time between dropping two tetrominos of a glyph
+    pub spawn_interval: Duration,
+    /// Cells per second the speed of a falling tetromino increases every
+    /// second. A tetromino coming to a stop starts over with `fall_speed`.
+    pub gravity: f64,
+}
+
+// The defaults match the original animation at 60 frames per second: one
+// cell per frame and a new tetromino every fourth frame.
+impl Default for Physics {
+    fn default() -> Self {
+        Self {
+            fall_speed: 60.0,
+            spawn_interval: Duration::from_millis(60),
+            gravity: 0.0,
         }
-        (Z, Degrees90) | (Z, Degrees270) => {
-            !canvas.is_empty_or_color(*x, *y, &empty)
-                || !canvas.is_empty_or_color(*x + 1, *y - 1, &empty)
+    }
+}
+
+/// How a [`DigitBoard`] brings the tetrominos of a new glyph into place
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum AnimationPolicy {
+    /// Drop the tetrominos one after the other with the fall speed of the
+    /// [`Physics`]
+    #[default]
+    Fall,
+    /// Place all tetrominos right away without any animation
+    Instant,
+    /// Drop the tetrominos one after the other, the given factor faster
+    /// than the fall speed and spawn interval of the [`Physics`]
+    FastFall(u32),
+}
+
+impl AnimationPolicy {
+    /// The given physics sped up for a fast fall
+    pub fn physics(&self, physics: Physics) -> Physics {
+        match self {
+            Self::Fall | Self::Instant => physics,
+            Self::FastFall(factor) => {
+                let factor = (*factor).max(1);
+                Physics {
+                    fall_speed: physics.fall_speed * factor as f64,
+                    spawn_interval: physics.spawn_interval / factor,
+                    ..physics
+                }
+            }
         }
     }
 }
 
+/// How the tetrominos of the previous glyph leave a [`DigitBoard`], when a
+/// new glyph is set
+#[derive(Debug, Clone, Copy, PartialEq, Default, ValueEnum)]
+#[clap(rename_all = "kebab-case")]
+pub enum Transition {
+    #[default]
+    #[value(help = "The old tetrominos fall out of the screen, while the new ones fall in")]
+    FallOut,
+    #[value(help = "The rows of the old glyph flash and vanish one after the other")]
+    LineClear,
+    #[value(help = "The old glyph bursts into single cells flying apart")]
+    Explode,
+    #[value(help = "The old glyph fades into the background")]
+    Fade,
+}
+
+// Time every row of a line clear flashes before it vanishes
+const LINE_CLEAR_INTERVAL: Duration = Duration::from_millis(40);
+const FADE_DURATION: Duration = Duration::from_millis(300);
+const EXPLODE_DURATION: Duration = Duration::from_millis(400);
+// Cells per second a fragment of an explosion flies apart for every cell it
+// is away from the center of the glyph, and the upward kick all of them get
+const EXPLODE_SPREAD: f64 = 12.0;
+const EXPLODE_LIFT: f64 = 20.0;
+const EXPLODE_GRAVITY: f64 = 150.0;
+
+// A single cell of a tetromino taken apart by a transition. Fragments never
+// collide with anything.
+#[derive(Clone, Copy)]
+struct Fragment {
+    x: f64,
+    y: f64,
+    velocity: (f64, f64),
+    color: Color,
+    // Time the fragment is shown (and flashes during the last
+    // `LINE_CLEAR_INTERVAL` of a line clear)
+    lifetime: Duration,
+}
+
 pub struct Board {
     tetrominos: Vec<Tetromino>,
+    fragments: Vec<Fragment>,
+    transition: Transition,
+    // Time since the current transition started
+    transition_elapsed: Duration,
     x_offset: i64,
     y_offset: i64,
     y_stop: i64,
+    scale: i64,
+    // Cells covered by the tetrominos of this board. Collisions are only
+    // checked against this grid and never against the rendered canvas.
+    occupied: HashSet<(i64, i64)>,
+    dimmed: bool,
+    flashing: bool,
+    // Time since the board started flashing
+    flash_elapsed: Duration,
+    physics: Physics,
 }
 
 impl Board {
+   // The board starts at `x_offset` pixels horizontally. Tetrominos are
+   // spawned at cell row `y_offset` and come to a hold at cell row `y_stop`.
+   // With the default scale of 1 a cell is exactly one pixel.
    pub fn new(x_offset: i64, y_offset: i64, y_stop: i64) -> Self {
         Self {
             tetrominos: vec![],
+            fragments: vec![],
+            transition: Transition::default(),
+            transition_elapsed: Duration::ZERO,
             x_offset,
             y_offset,
             y_stop,
+            scale: 1,
+            occupied: HashSet::new(),
+            dimmed: false,
+            flashing: false,
+            flash_elapsed: Duration::ZERO,
+            physics: Physics::default(),
         }
     }
 
+    // Render every cell as a square of `scale` x `scale` pixels
+    pub fn with_scale(mut self, scale: i64) -> Self {
+        self.scale = scale.max(1);
+        self
+    }
+
+    pub fn with_physics(mut self, physics: Physics) -> Self {
+        self.set_physics(physics);
+        self
+    }
+
+    // Tetrominos already falling keep their current speed
+    pub fn set_physics(&mut self, physics: Physics) {
+        self.physics = physics;
+    }
+
+    fn fill_cells<C: Canvas>(
+        &self,
+        canvas: &mut C,
+        x: i64,
+        y: i64,
+        width: u32,
+        height: u32,
+        color: &Color,
+    ) {
+        let scale = self.scale as u32;
+        canvas.filled_rect(
+            self.x_offset + x * self.scale,
+            y * self.scale,
+            width * scale,
+            height * scale,
+            color,
+        );
+    }
+
+    // Render all tetrominos, except those falling out, in a darker shade of
+    // their color (eg. to indicate a paused timer).
+    pub fn set_dimmed(&mut self, dimmed: bool) {
+        self.dimmed = dimmed;
+    }
+
+    // Let all tetrominos, except those falling out, blink in white (eg. to
+    // signal a finished countdown).
+    pub fn set_flashing(&mut self, flashing: bool) {
+        self.flashing = flashing;
+        self.flash_elapsed = Duration::ZERO;
+    }
+
     pub fn add_tetromino(
         &mut self,
         dx: i64,
@@ -359,12 +497,14 @@ impl Board {
         rotation: Rotation,
     ) {
         self.tetrominos.push(Tetromino {
-            x: self.x_offset + dx,
+            x: dx,
             y: self.y_offset + dy,
             color,
             shape,
             rotation,
             fall: FallState::In,
+            velocity: self.physics.fall_speed,
+            progress: 0.0,
         })
     }
     pub fn render<C: Canvas>(&self, canvas: &mut C) {
@@ -374,104 +514,387 @@ impl Board {
             x,
             y,
             color,
+            fall,
             ..
         } in self.tetrominos.iter()
         {
+            let flash_on = self.flashing
+                && (self.flash_elapsed.as_millis() / FLASH_INTERVAL.as_millis()).is_multiple_of(2);
+            // Tetrominos resting on others never reach the hold state, so
+            // everything but the tetrominos leaving the board is highlighted
+            let color = &if *fall == FallState::Out {
+                *color
+            } else if flash_on {
+                Color::from_rgb(255, 255, 255)
+            } else if self.dimmed {
+                dim_color(color)
+            } else {
+                *color
+            };
             use Rotation::*;
             use Shape::*;
             match (shape, rotation) {
                 (L, NoRotation) => {
-                    canvas.filled_rect(*x, *y - 2, 1, 2, color);
-                    canvas.filled_rect(*x + 1, *y - 2, 2, 1, color);
+                    self.fill_cells(canvas, *x, *y - 2, 1, 2, color);
+                    self.fill_cells(canvas, *x + 1, *y - 2, 2, 1, color);
                 }
                 (L, Degrees90) => {
-                    canvas.filled_rect(*x, *y - 3, 1, 3, color);
-                    canvas.filled_rect(*x - 1, *y - 3, 1, 1, color);
+                    self.fill_cells(canvas, *x, *y - 3, 1, 3, color);
+                    self.fill_cells(canvas, *x - 1, *y - 3, 1, 1, color);
                 }
                 (L, Degrees180) => {
-                    canvas.filled_rect(*x, *y - 1, 3, 1, color);
-                    canvas.filled_rect(*x + 2, *y - 2, 1, 1, color);
+                    self.fill_cells(canvas, *x, *y - 1, 3, 1, color);
+                    self.fill_cells(canvas, *x + 2, *y - 2, 1, 1, color);
                 }
                 (L, Degrees270) => {
-                    canvas.filled_rect(*x, *y - 3, 1, 3, color);
-                    canvas.filled_rect(*x + 1, *y - 1, 1, 1, color);
+                    self.fill_cells(canvas, *x, *y - 3, 1, 3, color);
+                    self.fill_cells(canvas, *x + 1, *y - 1, 1, 1, color);
                 }
                 (J, NoRotation) => {
-                    canvas.filled_rect(*x - 2, *y - 2, 2, 1, color);
-                    canvas.filled_rect(*x, *y - 2, 1, 2, color);
+                    self.fill_cells(canvas, *x - 2, *y - 2, 2, 1, color);
+                    self.fill_cells(canvas, *x, *y - 2, 1, 2, color);
                 }
                 (J, Degrees90) => {
-                    canvas.filled_rect(*x, *y - 1, 2, 1, color);
-                    canvas.filled_rect(*x + 1, *y - 3, 1, 2, color);
+                    self.fill_cells(canvas, *x, *y - 1, 2, 1, color);
+                    self.fill_cells(canvas, *x + 1, *y - 3, 1, 2, color);
                 }
                 (J, Degrees180) => {
-                    canvas.filled_rect(*x, *y - 2, 1, 2, color);
-                    canvas.filled_rect(*x + 1, *y - 1, 2, 1, color);
+                    self.fill_cells(canvas, *x, *y - 2, 1, 2, color);
+                    self.fill_cells(canvas, *x + 1, *y - 1, 2, 1, color);
                 }
                 (J, Degrees270) => {
-                    canvas.filled_rect(*x, *y - 3, 1, 3, color);
-                    canvas.filled_rect(*x + 1, *y - 3, 1, 1, color);
+                    self.fill_cells(canvas, *x, *y - 3, 1, 3, color);
+                    self.fill_cells(canvas, *x + 1, *y - 3, 1, 1, color);
                 }
                 (O, _) => {
-                    canvas.filled_rect(*x, *y - 2, 2, 2, color);
+                    self.fill_cells(canvas, *x, *y - 2, 2, 2, color);
                 }
                 (T, NoRotation) => {
-                    canvas.filled_rect(*x - 1, *y - 2, 3, 1, color);
-                    canvas.filled_rect(*x, *y - 1, 1, 1, color);
+                    self.fill_cells(canvas, *x - 1, *y - 2, 3, 1, color);
+                    self.fill_cells(canvas, *x, *y - 1, 1, 1, color);
                 }
                 (T, Degrees90) => {
-                    canvas.filled_rect(*x, *y - 3, 1, 3, color);
-                    canvas.filled_rect(*x - 1, *y - 2, 1, 1, color);
+                    self.fill_cells(canvas, *x, *y - 3, 1, 3, color);
+                    self.fill_cells(canvas, *x - 1, *y - 2, 1, 1, color);
                 }
                 (T, Degrees180) => {
-                    canvas.filled_rect(*x, *y - 1, 3, 1, color);
-                    canvas.filled_rect(*x + 1, *y - 2, 1, 1, color);
+                    self.fill_cells(canvas, *x, *y - 1, 3, 1, color);
+                    self.fill_cells(canvas, *x + 1, *y - 2, 1, 1, color);
                 }
                 (T, Degrees270) => {
-                    canvas.filled_rect(*x, *y - 3, 1, 3, color);
-                    canvas.filled_rect(*x + 1, *y - 2, 1, 1, color);
+                    self.fill_cells(canvas, *x, *y - 3, 1, 3, color);
+                    self.fill_cells(canvas, *x + 1, *y - 2, 1, 1, color);
                 }
                 (I, NoRotation) | (I, Degrees180) => {
-                    canvas.filled_rect(*x, *y - 1, 4, 1, color);
+                    self.fill_cells(canvas, *x, *y - 1, 4, 1, color);
                 }
                 (I, Degrees90) | (I, Degrees270) => {
-                    canvas.filled_rect(*x, *y - 4, 1, 4, color);
+                    self.fill_cells(canvas, *x, *y - 4, 1, 4, color);
                 }
                 (S, NoRotation) | (S, Degrees180) => {
-                    canvas.filled_rect(*x, *y - 1, 2, 1, color);
-                    canvas.filled_rect(*x + 1, *y - 2, 2, 1, color);
+                    self.fill_cells(canvas, *x, *y - 1, 2, 1, color);
+                    self.fill_cells(canvas, *x + 1, *y - 2, 2, 1, color);
                 }
                 (S, Degrees90) | (S, Degrees270) => {
-                    canvas.filled_rect(*x, *y - 2, 1, 2, color);
-                    canvas.filled_rect(*x - 1, *y - 3, 1, 2, color);
+                    self.fill_cells(canvas, *x, *y - 2, 1, 2, color);
+                    self.fill_cells(canvas, *x - 1, *y - 3, 1, 2, color);
                 }
                 (Z, NoRotation) | (Z, Degrees180) => {
-                    canvas.filled_rect(*x, *y - 1, 2, 1, color);
-                    canvas.filled_rect(*x - 1, *y - 2, 2, 1, color);
+                    self.fill_cells(canvas, *x, *y - 1, 2, 1, color);
+                    self.fill_cells(canvas, *x - 1, *y - 2, 2, 1, color);
                 }
                 (Z, Degrees90) | (Z, Degrees270) => {
-                    canvas.filled_rect(*x, *y - 2, 1, 2, color);
-                    canvas.filled_rect(*x + 1, *y - 3, 1, 2, color);
+                    self.fill_cells(canvas, *x, *y - 2, 1, 2, color);
+                    self.fill_cells(canvas, *x + 1, *y - 3, 1, 2, color);
                 }
             }
         }
+
+        for fragment in self.fragments.iter() {
+            let color = match self.transition {
+                Transition::LineClear
+                    if self.transition_elapsed + LINE_CLEAR_INTERVAL >= fragment.lifetime =>
+                {
+                    Color::from_rgb(255, 255, 255)
+                }
+                Transition::Fade => {
+                    let remaining = 1.0
+                        - (self.transition_elapsed.as_secs_f64() / FADE_DURATION.as_secs_f64())
+                            .min(1.0);
+                    let fade = |channel: u8| (channel as f64 * remaining) as u8;
+                    Color::from_rgb(
+                        fade(fragment.color.r),
+                        fade(fragment.color.g),
+                        fade(fragment.color.b),
+                    )
+                }
+                _ => fragment.color,
+            };
+            self.fill_cells(
+                canvas,
+                fragment.x.round() as i64,
+                fragment.y.round() as i64,
+                1,
+                1,
+                &color,
+            );
+        }
     }
 
-    pub fn update<C: Canvas>(&mut self, canvas: &C) {
-        for tetromino in self.tetrominos.iter_mut() {
-            if tetromino.fall != FallState::Hold
-                && !would_tetromino_collide_with_canvas(tetromino, canvas)
-            {
-                tetromino.y += 1;
+    fn would_tetromino_collide(&self, tetromino: &Tetromino) -> bool {
+        Self::collides(tetromino, &self.occupied)
+    }
+
+    fn collides(tetromino: &Tetromino, occupied: &HashSet<(i64, i64)>) -> bool {
+        let own: Vec<(i64, i64)> = tetromino.cells().collect();
+        own.iter()
+            .map(|&(x, y)| (x, y + 1))
+            .any(|cell| !own.contains(&cell) && occupied.contains(&cell))
+    }
+
+    // Advance the board by `dt`. Every tetromino moves the whole cells its
+    // velocity accumulated. The cells are moved one at a time, to never skip
+    // over a collision. The distance covered under gravity is exact, so it
+    // doesn't depend on how `dt` is split into frames.
+    pub fn update<C: Canvas>(&mut self, canvas: &C, dt: Duration) {
+        self.flash_elapsed += dt;
+        self.update_fragments(dt);
+        let dt = dt.as_secs_f64();
+        let Physics {
+            fall_speed,
+            gravity,
+            ..
+        } = self.physics;
+
+        // Tetrominos falling out are never blocked. They only need to move
+        // until they left the canvas, however fast they are.
+        let max_y = canvas.height() as i64 / self.scale + 4;
+        let mut steps: Vec<i64> = self
+            .tetrominos
+            .iter_mut()
+            .map(|tetromino| {
+                tetromino.progress += tetromino.velocity * dt + 0.5 * gravity * dt * dt;
+                tetromino.velocity += gravity * dt;
+                let cells = (tetromino.progress + CELL_EPSILON).floor();
+                tetromino.progress = (tetromino.progress - cells).max(0.0);
+                if tetromino.fall == FallState::Out {
+                    (cells as i64).min(max_y - tetromino.y + 1)
+                } else {
+                    cells as i64
+                }
+            })
+            .collect();
+
+        while steps.iter().any(|&cells| cells > 0) {
+            self.step(&mut steps);
+        }
+
+        for (tetromino, blocked) in self.tetrominos.iter_mut().zip(steps) {
+            if blocked < 0 {
+                tetromino.velocity = fall_speed;
+                tetromino.progress = 0.0;
             }
+        }
 
+        self.tetrominos.retain(|tetromino| tetromino.y <= max_y);
+    }
+
+    fn update_fragments(&mut self, dt: Duration) {
+        self.transition_elapsed += dt;
+        let elapsed = self.transition_elapsed;
+        let dt = dt.as_secs_f64();
+        for fragment in self.fragments.iter_mut() {
+            fragment.x += fragment.velocity.0 * dt;
+            fragment.y += fragment.velocity.1 * dt;
+            if self.transition == Transition::Explode {
+                fragment.y += 0.5 * EXPLODE_GRAVITY * dt * dt;
+                fragment.velocity.1 += EXPLODE_GRAVITY * dt;
+            }
+        }
+        self.fragments.retain(|fragment| fragment.lifetime > elapsed);
+    }
+
+    // Move every tetromino with cells left to move down by a single cell.
+    // Tetrominos which could not move are marked with -1. The occupancy grid
+    // is only rebuilt once per step, so all tetrominos move based on the
+    // positions of the previous step. Tetrominos falling out only collide
+    // with each other, so they pass the ones kept by a morph.
+    fn step(&mut self, steps: &mut [i64]) {
+        self.occupied = self.tetrominos.iter().flat_map(Tetromino::cells).collect();
+        let falling_out: HashSet<(i64, i64)> = self
+            .tetrominos
+            .iter()
+            .filter(|tetromino| tetromino.fall == FallState::Out)
+            .flat_map(Tetromino::cells)
+            .collect();
+
+        for (i, cells) in steps.iter_mut().enumerate() {
+            if *cells <= 0 {
+                continue;
+            }
+            let tetromino = &self.tetrominos[i];
+            let collides = match tetromino.fall {
+                FallState::Hold => true,
+                FallState::Out => Self::collides(tetromino, &falling_out),
+                FallState::In => self.would_tetromino_collide(tetromino),
+            };
+            if !collides {
+                self.tetrominos[i].y += 1;
+                *cells -= 1;
+            } else {
+                *cells = -1;
+            }
+        }
+
+        for tetromino in self.tetrominos.iter_mut() {
             if tetromino.y == self.y_stop && tetromino.fall != FallState::Out {
                 tetromino.fall = FallState::Hold;
             }
         }
+    }
 
-        self.tetrominos
-            .retain(|tetromino| tetromino.y <= canvas.height() as i64 + 4);
+    /// Whether a tetromino added at the given position would be clear of all
+    /// tetrominos already on the board. Its columns must be empty above it
+    /// and right below it, so it neither overlaps nor touches any other
+    /// tetromino while falling behind them.
+    pub fn has_room_for(&self, dx: i64, dy: i64, shape: Shape, rotation: Rotation) -> bool {
+        let y = self.y_offset + dy;
+        let cells: Vec<(i64, i64)> = self.tetrominos.iter().flat_map(Tetromino::cells).collect();
+        shape.cells(rotation).iter().all(|&(cx, cy)| {
+            !cells
+                .iter()
+                .any(|&(x, other_y)| x == dx + cx && other_y <= y + cy + 1)
+        })
+    }
+
+    /// Add a tetromino right at the position it would fall to, without
+    /// animating the fall
+    pub fn place_tetromino(&mut self, dx: i64, color: Color, shape: Shape, rotation: Rotation) {
+        self.occupied = self
+            .tetrominos
+            .iter()
+            .filter(|tetromino| tetromino.fall != FallState::Out)
+            .flat_map(Tetromino::cells)
+            .collect();
+
+        let mut tetromino = Tetromino {
+            x: dx,
+            y: self.y_offset,
+            color,
+            shape,
+            rotation,
+            fall: FallState::In,
+            velocity: self.physics.fall_speed,
+            progress: 0.0,
+        };
+        while tetromino.y < self.y_stop && !self.would_tetromino_collide(&tetromino) {
+            tetromino.y += 1;
+        }
+        if tetromino.y == self.y_stop {
+            tetromino.fall = FallState::Hold;
+        }
+        self.tetrominos.push(tetromino);
+    }
+
+    // Cell rows the tetrominos of an animation come to rest at on this
+    // board, when dropped one after the other onto the given tetrominos
+    fn resting_rows(&self, resting: Vec<Tetromino>, animation: &Animation) -> Vec<i64> {
+        let mut board = Board::new(self.x_offset, self.y_offset, self.y_stop);
+        board.tetrominos = resting;
+        animation
+            .iter()
+            .map(|&FallingTetromino { shape, rotation, dx }| {
+                board.place_tetromino(dx, Color::from_rgb(0, 0, 0), shape, rotation);
+                board.tetrominos[board.tetrominos.len() - 1].y
+            })
+            .collect()
+    }
+
+    /// Turn the board into the final state of another animation, by only
+    /// exchanging the tetrominos which differ. Tetrominos already resting
+    /// where a tetromino of the animation would come to rest are kept in
+    /// place (and recolored), all others leave the board using the given
+    /// transition. The tetrominos of the animation which still need to be
+    /// dropped are returned.
+    ///
+    /// Returns `None` and leaves the board untouched, if no tetromino could
+    /// be kept.
+    pub fn morph_into(
+        &mut self,
+        animation: &Animation,
+        transition: Transition,
+        color: impl Fn(Shape) -> Color,
+    ) -> Option<Animation> {
+        let rows = self.resting_rows(vec![], animation);
+        let mut available: Vec<usize> = (0..self.tetrominos.len())
+            .filter(|&i| self.tetrominos[i].fall != FallState::Out)
+            .collect();
+        // The tetromino of the board kept for every tetromino of the animation
+        let mut kept: Vec<Option<usize>> = animation
+            .iter()
+            .zip(rows.iter())
+            .map(|(falling, &y)| {
+                available
+                    .iter()
+                    .position(|&i| {
+                        let tetromino = &self.tetrominos[i];
+                        tetromino.shape == falling.shape
+                            && tetromino.rotation == falling.rotation
+                            && tetromino.x == falling.dx
+                            && tetromino.y == y
+                    })
+                    .map(|position| available.remove(position))
+            })
+            .collect();
+
+        // A missing tetromino may not be able to fall past the kept ones
+        // into its place. Everything coming after it in the animation is
+        // dropped again in that case, just like building the glyph from
+        // scratch.
+        let missing = loop {
+            let resting = kept
+                .iter()
+                .flatten()
+                .map(|&i| Tetromino {
+                    fall: FallState::Hold,
+                    ..self.tetrominos[i]
+                })
+                .collect();
+            let missing: Vec<usize> = (0..animation.len()).filter(|&n| kept[n].is_none()).collect();
+            let missing_animation: Animation = missing.iter().map(|&n| animation[n]).collect();
+            let blocked = self
+                .resting_rows(resting, &missing_animation)
+                .into_iter()
+                .zip(missing.iter())
+                .find(|&(y, &n)| y != rows[n]);
+            match blocked {
+                None => break missing_animation,
+                Some((_, &n)) if kept[n + 1..].iter().any(Option::is_some) => {
+                    kept[n + 1..].fill(None);
+                }
+                Some(_) => return None,
+            }
+        };
+        if kept.iter().all(Option::is_none) {
+            return None;
+        }
+
+        // Tetrominos already falling out are left alone
+        let kept: Vec<usize> = kept.into_iter().flatten().collect();
+        let mut leaving = vec![];
+        for (i, mut tetromino) in std::mem::take(&mut self.tetrominos).into_iter().enumerate() {
+            if tetromino.fall != FallState::Out && !kept.contains(&i) {
+                leaving.push(tetromino);
+                continue;
+            }
+            if tetromino.fall != FallState::Out {
+                tetromino.fall = FallState::Hold;
+                tetromino.color = color(tetromino.shape);
+            }
+            self.tetrominos.push(tetromino);
+        }
+        self.remove_with(leaving, transition);
+        Some(missing)
     }
 
     pub fn initiate_fall_out(&mut self) {
@@ -480,16 +903,134 @@ impl Board {
         }
     }
 
+    /// Let the tetrominos leave the board using the given transition. All
+    /// but a fall out take the tetrominos apart into single cells, which
+    /// replace the fragments of any earlier transition. Tetrominos already
+    /// falling out are left alone.
+    pub fn initiate_transition(&mut self, transition: Transition) {
+        if transition == Transition::FallOut {
+            self.initiate_fall_out();
+            return;
+        }
+
+        let (leaving, staying): (Vec<Tetromino>, Vec<Tetromino>) =
+            std::mem::take(&mut self.tetrominos)
+                .into_iter()
+                .partition(|tetromino| tetromino.fall != FallState::Out);
+        self.tetrominos = staying;
+        self.remove_with(leaving, transition);
+    }
+
+    // Let tetrominos already taken off the board leave it using the given
+    // transition. Falling out puts them back as tetrominos falling out.
+    fn remove_with(&mut self, mut leaving: Vec<Tetromino>, transition: Transition) {
+        if transition == Transition::FallOut {
+            for tetromino in leaving.iter_mut() {
+                tetromino.fall = FallState::Out;
+            }
+            self.tetrominos.append(&mut leaving);
+            return;
+        }
+
+        let cells: Vec<(i64, i64, Color)> = leaving
+            .iter()
+            .flat_map(|tetromino| tetromino.cells().map(|(x, y)| (x, y, tetromino.color)))
+            .collect();
+        if cells.is_empty() {
+            return;
+        }
+
+        let count = cells.len() as f64;
+        let center_x = cells.iter().map(|&(x, _, _)| x as f64).sum::<f64>() / count;
+        let center_y = cells.iter().map(|&(_, y, _)| y as f64).sum::<f64>() / count;
+        let top = cells.iter().map(|&(_, y, _)| y).min().unwrap_or(0);
+        // Rows of a line clear vanish from the top to the bottom
+        self.fragments = cells
+            .into_iter()
+            .map(|(x, y, color)| {
+                let (velocity, lifetime) = match transition {
+                    Transition::LineClear => {
+                        ((0.0, 0.0), LINE_CLEAR_INTERVAL * (y - top + 1) as u32)
+                    }
+                    Transition::Explode => (
+                        (
+                            (x as f64 - center_x) * EXPLODE_SPREAD,
+                            (y as f64 - center_y) * EXPLODE_SPREAD - EXPLODE_LIFT,
+                        ),
+                        EXPLODE_DURATION,
+                    ),
+                    Transition::Fade | Transition::FallOut => ((0.0, 0.0), FADE_DURATION),
+                };
+                Fragment {
+                    x: x as f64,
+                    y: y as f64,
+                    velocity,
+                    color,
+                    lifetime,
+                }
+            })
+            .collect();
+        self.transition = transition;
+        self.transition_elapsed = Duration::ZERO;
+    }
+
+    /// Whether the tetrominos of a transition (other than a fall out) are
+    /// still on their way out
+    pub fn is_transitioning(&self) -> bool {
+        !self.fragments.is_empty()
+    }
+
+    /// Remove all tetrominos at once
+    pub fn clear(&mut self) {
+        self.tetrominos.clear();
+        self.fragments.clear();
+    }
+
+    /// Whether all tetrominos left the canvas (or none were added yet)
+    pub fn is_empty(&self) -> bool {
+        self.tetrominos.is_empty() && self.fragments.is_empty()
+    }
+
+    /// Whether every tetromino came to a hold, either at the bottom of the
+    /// board or on top of another one, and nothing is leaving the board
+    pub fn is_settled(&self) -> bool {
+        let occupied = self.occupied_cells();
+        self.fragments.is_empty()
+            && self.tetrominos.iter().all(|tetromino| match tetromino.fall {
+                FallState::Hold => true,
+                FallState::Out => false,
+                FallState::In => {
+                    let own: Vec<(i64, i64)> = tetromino.cells().collect();
+                    own.iter()
+                        .any(|&(x, y)| !own.contains(&(x, y + 1)) && occupied.contains(&(x, y + 1)))
+                }
+            })
+    }
+
+    /// The cells covered by the tetrominos on the board, except those
+    /// falling out
+    pub fn occupied_cells(&self) -> HashSet<(i64, i64)> {
+        self.tetrominos
+            .iter()
+            .filter(|tetromino| tetromino.fall != FallState::Out)
+            .flat_map(Tetromino::cells)
+            .collect()
+    }
 }
 
 pub struct DigitBoard {
     board: Board,
-    digit: Digit,
+    glyph: Glyph,
     animation: Animation,
     position: usize,
     colorscheme: Colorscheme,
     index: usize,
-    updates_since_last_anim: usize,
+    since_last_anim: Duration,
+    policy: AnimationPolicy,
+    physics: Physics,
+    diffing: bool,
+    transition: Transition,
+    font: Arc<Font>,
 }
 
 impl DigitBoard {
@@ -498,26 +1039,128 @@ impl DigitBoard {
         x_offset: i64,
         y_stop: i64,
         colorscheme: Colorscheme,
-        digit: Digit,
+        glyph: Glyph,
     ) -> Self {
         Self {
             board: Board::new(x_offset, 0, y_stop),
             position,
-            digit,
-            animation: digit.into(),
+            glyph,
+            animation: glyph.into(),
             colorscheme,
             index: 0,
-            updates_since_last_anim: 0,
+            since_last_anim: Duration::ZERO,
+            policy: AnimationPolicy::default(),
+            physics: Physics::default(),
+            diffing: false,
+            transition: Transition::default(),
+            font: Arc::default(),
         }
     }
 
-    pub fn update<C: Canvas>(&mut self, canvas: &C) {
-        if self.index < self.animation.len() && self.updates_since_last_anim > 3{ 
-            let FallingTetromino {
-                shape,
-                rotation,
-                dx,
-            } = self.animation[self.index];
+    pub fn with_scale(mut self, scale: i64) -> Self {
+        self.board = self.board.with_scale(scale);
+        self
+    }
+
+    // Instant placement is meant for positions changing too fast for the
+    // animation to keep up (eg. fractions of a second), or for a static
+    // display.
+    pub fn with_animation_policy(mut self, policy: AnimationPolicy) -> Self {
+        self.set_animation_policy(policy);
+        self
+    }
+
+    pub fn set_animation_policy(&mut self, policy: AnimationPolicy) {
+        self.policy = policy;
+        self.board.set_physics(policy.physics(self.physics));
+    }
+
+    pub fn with_physics(mut self, physics: Physics) -> Self {
+        self.set_physics(physics);
+        self
+    }
+
+    pub fn set_physics(&mut self, physics: Physics) {
+        self.physics = physics;
+        self.board.set_physics(self.policy.physics(physics));
+    }
+
+    // Only exchange the tetrominos which differ between the old and the new
+    // glyph (eg. from 8 to 9), instead of rebuilding the whole glyph. Falls
+    // back to rebuilding, whenever the new tetrominos could not fall into
+    // place around the kept ones.
+    pub fn with_diffing(mut self, diffing: bool) -> Self {
+        self.set_diffing(diffing);
+        self
+    }
+
+    pub fn set_diffing(&mut self, diffing: bool) {
+        self.diffing = diffing;
+    }
+
+    // Except for a fall out, the new glyph only drops in once the previous
+    // one is gone
+    pub fn with_transition(mut self, transition: Transition) -> Self {
+        self.set_transition(transition);
+        self
+    }
+
+    pub fn set_transition(&mut self, transition: Transition) {
+        self.transition = transition;
+    }
+
+    // The font is shared by all boards of a clock. The current glyph is
+    // rebuilt from scratch using the new font.
+    pub fn with_font(mut self, font: Arc<Font>) -> Self {
+        self.set_font(font);
+        self
+    }
+
+    pub fn set_font(&mut self, font: Arc<Font>) {
+        self.font = font;
+        self.board.initiate_fall_out();
+        self.animation = self.font.animation(self.glyph);
+        self.index = 0;
+    }
+
+    pub fn update<C: Canvas>(&mut self, canvas: &C, dt: Duration) {
+        if self.policy == AnimationPolicy::Instant {
+            for &FallingTetromino { shape, rotation, dx } in &self.animation[self.index..] {
+                let color = self.colorscheme.apply(shape, self.glyph, self.position);
+                self.board.place_tetromino(dx, color, shape, rotation);
+            }
+            self.index = self.animation.len();
+        }
+
+        // The first tetromino of a digit is dropped right away, so a new
+        // digit starts falling in the very update it has been set. Every
+        // further one is due a spawn interval after the previous one. The
+        // board is advanced up to the moment a tetromino is due, so it falls
+        // in at the same time no matter how many frames the interval spans.
+        // Tetrominos must not touch while falling, so the next one waits
+        // while slowly falling ones still block the spawn area.
+        let spawn_interval = self.policy.physics(self.physics).spawn_interval;
+        let mut remaining = dt;
+        while let Some(&FallingTetromino {
+            shape,
+            rotation,
+            dx,
+        }) = self.animation.get(self.index)
+        {
+            let wait = if self.index == 0 {
+                Duration::ZERO
+            } else {
+                spawn_interval.saturating_sub(self.since_last_anim)
+            };
+            if wait > remaining {
+                break;
+            }
+            self.board.update(canvas, wait);
+            self.since_last_anim += wait;
+            remaining -= wait;
+            if self.board.is_transitioning() || !self.board.has_room_for(dx, 0, shape, rotation) {
+                break;
+            }
 
             // let color = Color::from_rgb(
             // rand:: random::<u8>(),
@@ -526,27 +1169,444 @@ impl DigitBoard {
             // );
 
             // self.board.add_tetromino( self.x_offset + dx, 0, shape.into(), shape, rotation);
-            let color = self.colorscheme.apply(shape, self.digit, self.position);
+            let color = self.colorscheme.apply(shape, self.glyph, self.position);
             self.board.add_tetromino(dx, 0, color, shape, rotation);
-            
             self.index += 1;
-            self.updates_since_last_anim = 0;
+            // The board has been advanced right to the moment the tetromino
+            // dropped, so no part of the interval is lost
+            self.since_last_anim = Duration::ZERO;
         }
 
-        
-        self.board.update(canvas);
-        self.updates_since_last_anim += 1;
+        self.board.update(canvas, remaining);
+        self.since_last_anim += remaining;
     }
 
     pub fn render<C: Canvas>(&self, canvas: &mut C) {
         self.board.render(canvas);
     }
 
+    pub fn set_dimmed(&mut self, dimmed: bool) {
+        self.board.set_dimmed(dimmed);
+    }
+
+    pub fn set_flashing(&mut self, flashing: bool) {
+        self.board.set_flashing(flashing);
+    }
+
     pub fn set_digit(&mut self, digit: Digit) {
-        self.board.initiate_fall_out();
-        self.digit = digit;
-        self.animation = digit.into();
+        self.set_glyph(digit.into());
+    }
+
+    /// Whether all tetrominos of the glyph have been dropped and came to a
+    /// hold
+    pub fn is_settled(&self) -> bool {
+        self.index == self.animation.len() && self.board.is_settled()
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// The board holding the tetrominos dropped so far. The remaining
+    /// tetrominos of the animation are discarded.
+    pub fn into_board(self) -> Board {
+        self.board
+    }
+
+    // With instant placement the previous glyph disappears right away, as
+    // falling tetrominos would get stuck on the newly placed ones
+    pub fn set_glyph(&mut self, glyph: Glyph) {
+        let animation = self.font.animation(glyph);
+        let (colorscheme, position) = (self.colorscheme, self.position);
+        let instant = self.policy == AnimationPolicy::Instant;
+        let missing = if self.diffing && !instant {
+            self.board.morph_into(&animation, self.transition, |shape| {
+                colorscheme.apply(shape, glyph, position)
+            })
+        } else {
+            None
+        };
+        self.animation = missing.unwrap_or_else(|| {
+            if instant {
+                self.board.clear();
+            } else {
+                self.board.initiate_transition(self.transition);
+            }
+            animation
+        });
+        // The spawn timer starts over with the first tetromino of the glyph
+        self.glyph = glyph;
         self.index = 0;
-        self.updates_since_last_anim = 0;
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pixel_loop::canvas::InMemoryCanvas;
+    use Rotation::*;
+    use Shape::*;
+
+    const RECOLORED: Color = Color::from_rgb(1, 2, 3);
+
+    fn animation(tetrominos: &[(Shape, Rotation, i64)]) -> Animation {
+        tetrominos
+            .iter()
+            .map(|&(shape, rotation, dx)| FallingTetromino::new(shape, rotation, dx))
+            .collect()
+    }
+
+    fn board(animation: &Animation) -> Board {
+        let mut board = Board::new(0, 0, 20);
+        for &FallingTetromino { shape, rotation, dx } in animation {
+            board.place_tetromino(dx, Color::from_rgb(255, 255, 255), shape, rotation);
+        }
+        board
+    }
+
+    fn positions(board: &Board) -> Vec<(Shape, i64, i64, FallState)> {
+        board
+            .tetrominos
+            .iter()
+            .map(|tetromino| (tetromino.shape, tetromino.x, tetromino.y, tetromino.fall))
+            .collect()
+    }
+
+    #[test]
+    fn morph_into_the_same_glyph_keeps_everything() {
+        let target = animation(&[(O, NoRotation, 0), (I, Degrees90, 2), (O, NoRotation, 0)]);
+        let mut board = board(&target);
+
+        let missing = board.morph_into(&target, Transition::Fade, |_| RECOLORED);
+
+        assert_eq!(missing, Some(vec![]));
+        assert_eq!(board.tetrominos.len(), 3);
+        assert!(board
+            .tetrominos
+            .iter()
+            .all(|tetromino| tetromino.fall == FallState::Hold && tetromino.color == RECOLORED));
+        assert!(!board.is_transitioning());
+    }
+
+    #[test]
+    fn morph_into_drops_only_differing_tetrominos() {
+        let mut board = board(&animation(&[(O, NoRotation, 0), (O, NoRotation, 2)]));
+        let target = animation(&[(O, NoRotation, 0), (O, NoRotation, 4)]);
+
+        let missing = board.morph_into(&target, Transition::Fade, |_| RECOLORED);
+
+        assert_eq!(missing, Some(animation(&[(O, NoRotation, 4)])));
+        assert_eq!(positions(&board), vec![(O, 0, 20, FallState::Hold)]);
+        // The differing tetromino fades away instead of vanishing at once
+        assert_eq!(board.fragments.len(), 4);
+        assert!(board
+            .fragments
+            .iter()
+            .all(|fragment| fragment.x >= 2.0 && fragment.x < 4.0));
+    }
+
+    #[test]
+    fn morph_into_lets_differing_tetrominos_fall_past_kept_ones() {
+        let mut board = board(&animation(&[(O, NoRotation, 0), (O, NoRotation, 0)]));
+        let target = animation(&[(O, NoRotation, 0), (I, Degrees90, 4)]);
+
+        let missing = board.morph_into(&target, Transition::FallOut, |_| RECOLORED);
+
+        assert_eq!(missing, Some(animation(&[(I, Degrees90, 4)])));
+        assert_eq!(
+            positions(&board),
+            vec![(O, 0, 20, FallState::Hold), (O, 0, 18, FallState::Out)]
+        );
+
+        let canvas = InMemoryCanvas::new(8, 20, &Color::from_rgb(0, 0, 0));
+        for _ in 0..100 {
+            board.update(&canvas, Duration::from_millis(50));
+        }
+        assert_eq!(positions(&board), vec![(O, 0, 20, FallState::Hold)]);
+    }
+
+    #[test]
+    fn morph_into_drops_everything_after_a_blocked_tetromino_again() {
+        // The top square of the board rests where the last one of the target
+        // rests, but the square missing below it could never fall past it
+        let mut board = board(&animation(&[
+            (O, NoRotation, 0),
+            (I, Degrees90, 2),
+            (I, Degrees90, 3),
+            (O, NoRotation, 2),
+        ]));
+        let target = animation(&[
+            (O, NoRotation, 0),
+            (O, NoRotation, 2),
+            (O, NoRotation, 2),
+            (O, NoRotation, 2),
+        ]);
+
+        let missing = board.morph_into(&target, Transition::Fade, |_| RECOLORED);
+
+        assert_eq!(missing, Some(target[1..].to_vec()));
+        assert_eq!(positions(&board), vec![(O, 0, 20, FallState::Hold)]);
+        assert_eq!(board.fragments.len(), 12);
+    }
+
+    #[test]
+    fn morph_into_without_shared_tetrominos_leaves_the_board_untouched() {
+        let mut board = board(&animation(&[(O, NoRotation, 0)]));
+        let target = animation(&[(O, NoRotation, 2)]);
+
+        let missing = board.morph_into(&target, Transition::Fade, |_| RECOLORED);
+
+        assert_eq!(missing, None);
+        assert_eq!(positions(&board), vec![(O, 0, 20, FallState::Hold)]);
+        assert_ne!(board.tetrominos[0].color, RECOLORED);
+        assert!(!board.is_transitioning());
+    }
+
+    #[test]
+    fn morph_into_leaves_tetrominos_falling_out_alone() {
+        let mut board = board(&animation(&[(O, NoRotation, 0), (O, NoRotation, 2)]));
+        board.tetrominos[1].fall = FallState::Out;
+        let target = animation(&[(O, NoRotation, 0), (O, NoRotation, 2)]);
+
+        let missing = board.morph_into(&target, Transition::Fade, |_| RECOLORED);
+
+        assert_eq!(missing, Some(animation(&[(O, NoRotation, 2)])));
+        assert_eq!(
+            positions(&board),
+            vec![(O, 0, 20, FallState::Hold), (O, 2, 20, FallState::Out)]
+        );
+        assert_ne!(board.tetrominos[1].color, RECOLORED);
+        assert!(!board.is_transitioning());
+    }
+
+    #[test]
+    fn transitions_take_every_tetromino_apart() {
+        let glyph = animation(&[(O, NoRotation, 0), (I, Degrees90, 2), (T, NoRotation, 1)]);
+        for transition in [Transition::LineClear, Transition::Explode, Transition::Fade] {
+            let mut board = board(&glyph);
+
+            board.initiate_transition(transition);
+
+            assert!(board.tetrominos.is_empty(), "{:?}", transition);
+            assert_eq!(board.fragments.len(), 12, "{:?}", transition);
+            assert!(board.is_transitioning(), "{:?}", transition);
+        }
+    }
+
+    #[test]
+    fn transition_replaces_earlier_fragments() {
+        let mut board = board(&animation(&[(O, NoRotation, 0)]));
+        board.initiate_transition(Transition::Explode);
+        board.place_tetromino(4, Color::from_rgb(255, 255, 255), I, Degrees90);
+
+        board.initiate_transition(Transition::Fade);
+
+        assert_eq!(board.transition, Transition::Fade);
+        assert_eq!(board.fragments.len(), 4);
+        assert!(board.fragments.iter().all(|fragment| fragment.x == 4.0));
+    }
+
+    #[test]
+    fn transition_leaves_tetrominos_falling_out_alone() {
+        let mut board = board(&animation(&[(O, NoRotation, 0), (O, NoRotation, 2)]));
+        board.tetrominos[1].fall = FallState::Out;
+
+        board.initiate_transition(Transition::LineClear);
+
+        assert_eq!(positions(&board), vec![(O, 2, 20, FallState::Out)]);
+        assert_eq!(board.fragments.len(), 4);
+        assert!(board.fragments.iter().all(|fragment| fragment.x < 2.0));
+    }
+
+    #[test]
+    fn board_settles_once_the_transition_is_over() {
+        let canvas = InMemoryCanvas::new(8, 20, &Color::from_rgb(0, 0, 0));
+        for transition in [Transition::LineClear, Transition::Explode, Transition::Fade] {
+            let mut board = board(&animation(&[(O, NoRotation, 0), (I, Degrees90, 2)]));
+            board.initiate_transition(transition);
+            assert!(!board.is_settled(), "{:?}", transition);
+
+            for _ in 0..50 {
+                board.update(&canvas, Duration::from_millis(20));
+            }
+
+            assert!(board.is_empty(), "{:?}", transition);
+            assert!(board.is_settled(), "{:?}", transition);
+        }
+    }
+
+    // Positions of the tetrominos of a glyph every 1/15 of a second, while
+    // updating the board `frames` times per 1/15 of a second
+    fn fall_in(physics: Physics, frames: u32) -> Vec<Vec<(Shape, i64, i64, FallState)>> {
+        let canvas = InMemoryCanvas::new(8, 40, &Color::from_rgb(0, 0, 0));
+        let glyph = Glyph::from_char('8').unwrap();
+        let mut board =
+            DigitBoard::new(0, 0, 30, Colorscheme::default(), glyph).with_physics(physics);
+        let dt = Duration::from_secs(1) / 60 * 4 / frames;
+        (0..45)
+            .map(|_| {
+                for _ in 0..frames {
+                    board.update(&canvas, dt);
+                }
+                positions(board.board())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn falling_looks_the_same_at_any_frame_rate() {
+        for physics in [
+            Physics::default(),
+            Physics {
+                fall_speed: 7.0,
+                spawn_interval: Duration::from_millis(250),
+                gravity: 90.0,
+            },
+        ] {
+            let at_15_fps = fall_in(physics, 1);
+            let at_60_fps = fall_in(physics, 4);
+            assert_eq!(at_15_fps, at_60_fps, "{:?}", physics);
+            assert!(at_60_fps[0].len() < at_60_fps[44].len());
+        }
+    }
+
+    #[test]
+    fn fast_tetrominos_falling_out_leave_within_one_update() {
+        let canvas = InMemoryCanvas::new(8, 20, &Color::from_rgb(0, 0, 0));
+        let mut board = board(&animation(&[(O, NoRotation, 0), (I, Degrees90, 2)]));
+        board.initiate_fall_out();
+        for tetromino in board.tetrominos.iter_mut() {
+            tetromino.velocity = 1e15;
+        }
+
+        board.update(&canvas, Duration::from_millis(20));
+
+        assert!(board.is_empty());
+    }
+
+    fn digit_board(policy: AnimationPolicy, physics: Physics) -> DigitBoard {
+        DigitBoard::new(0, 0, 30, Colorscheme::default(), Glyph::from_char('8').unwrap())
+            .with_physics(physics)
+            .with_animation_policy(policy)
+    }
+
+    #[test]
+    fn instant_policy_completes_the_glyph_in_one_update() {
+        let canvas = InMemoryCanvas::new(8, 40, &Color::from_rgb(0, 0, 0));
+        let mut board = digit_board(AnimationPolicy::Instant, Physics::default());
+
+        board.update(&canvas, Duration::from_millis(16));
+
+        assert_eq!(board.board().tetrominos.len(), board.animation.len());
+        assert!(board.is_settled());
+    }
+
+    #[test]
+    fn fast_fall_speeds_up_falling_and_spawning() {
+        let physics = Physics {
+            fall_speed: 30.0,
+            spawn_interval: Duration::from_millis(100),
+            gravity: 5.0,
+        };
+        let board = digit_board(AnimationPolicy::FastFall(4), physics);
+
+        assert_eq!(
+            board.board().physics,
+            Physics {
+                fall_speed: 120.0,
+                spawn_interval: Duration::from_millis(25),
+                gravity: 5.0,
+            }
+        );
+        assert_eq!(AnimationPolicy::Fall.physics(physics), physics);
+        assert_eq!(AnimationPolicy::FastFall(0).physics(physics), physics);
+    }
+
+    #[test]
+    fn fast_fall_drops_the_tetrominos_faster() {
+        let canvas = InMemoryCanvas::new(8, 40, &Color::from_rgb(0, 0, 0));
+        let physics = Physics {
+            spawn_interval: Duration::from_millis(100),
+            ..Physics::default()
+        };
+        let dropped = |policy| {
+            let mut board = digit_board(policy, physics);
+            for _ in 0..12 {
+                board.update(&canvas, Duration::from_millis(20));
+            }
+            board.index
+        };
+
+        assert_eq!(dropped(AnimationPolicy::Fall), 3);
+        assert_eq!(dropped(AnimationPolicy::FastFall(2)), 5);
+    }
+
+    #[test]
+    fn tetrominos_fall_with_the_speed_and_gravity_of_the_physics() {
+        let canvas = InMemoryCanvas::new(8, 100, &Color::from_rgb(0, 0, 0));
+        for (gravity, cells) in [(0.0, 10), (20.0, 20)] {
+            let mut board = Board::new(0, 0, 100);
+            board.set_physics(Physics {
+                fall_speed: 10.0,
+                gravity,
+                ..Physics::default()
+            });
+            board.add_tetromino(0, 0, Color::from_rgb(255, 255, 255), O, NoRotation);
+
+            for _ in 0..10 {
+                board.update(&canvas, Duration::from_millis(100));
+            }
+
+            assert_eq!(board.tetrominos[0].y, cells, "{}", gravity);
+        }
+    }
+
+    #[test]
+    fn tetromino_lands_on_top_of_another_one() {
+        let canvas = InMemoryCanvas::new(8, 40, &Color::from_rgb(0, 0, 0));
+        let mut board = board(&animation(&[(I, Degrees90, 1)]));
+        board.add_tetromino(0, 0, Color::from_rgb(255, 255, 255), O, NoRotation);
+
+        for _ in 0..50 {
+            board.update(&canvas, Duration::from_millis(20));
+        }
+
+        // The square rests on top of the I, overhanging it on the left
+        assert_eq!(
+            positions(&board),
+            vec![(I, 1, 20, FallState::Hold), (O, 0, 16, FallState::In)]
+        );
+        assert!(board.is_settled());
+    }
+
+    #[test]
+    fn tetromino_falling_out_passes_settled_ones() {
+        let canvas = InMemoryCanvas::new(8, 40, &Color::from_rgb(0, 0, 0));
+        let mut board = board(&animation(&[(O, NoRotation, 0)]));
+        board.add_tetromino(0, 0, Color::from_rgb(255, 255, 255), O, NoRotation);
+        board.tetrominos[1].fall = FallState::Out;
+
+        board.update(&canvas, Duration::from_millis(400));
+
+        assert_eq!(
+            positions(&board),
+            vec![(O, 0, 20, FallState::Hold), (O, 0, 24, FallState::Out)]
+        );
+    }
+
+    #[test]
+    fn spawn_needs_free_columns() {
+        let canvas = InMemoryCanvas::new(8, 40, &Color::from_rgb(0, 0, 0));
+        let mut board = Board::new(0, 0, 20);
+        board.add_tetromino(2, 0, Color::from_rgb(255, 255, 255), I, Degrees90);
+
+        assert!(!board.has_room_for(2, 0, O, NoRotation));
+        assert!(!board.has_room_for(1, 0, O, NoRotation));
+        assert!(!board.has_room_for(0, 0, I, NoRotation));
+        assert!(board.has_room_for(0, 0, O, NoRotation));
+        assert!(board.has_room_for(3, 0, O, NoRotation));
+
+        // Once the tetromino fell far enough, its columns are free again
+        board.update(&canvas, Duration::from_millis(100));
+        assert!(board.has_room_for(2, 0, O, NoRotation));
+    }
+}
@@ -0,0 +1,33 @@
+use std::cell::Cell;
+
+use chrono::{DateTime, Duration, Utc};
+
+// The time is frozen per thread, so tests running in parallel can each use
+// their own time
+thread_local! {
+    static FROZEN: Cell<Option<DateTime<Utc>>> = const { Cell::new(None) };
+}
+
+/// The current time all clocks, timers and stopwatches are based on. This is
+/// the system time, unless it was frozen on the current thread.
+pub fn now() -> DateTime<Utc> {
+    FROZEN.with(Cell::get).unwrap_or_else(Utc::now)
+}
+
+/// Stop the time on the current thread at the given point in time. Until the
+/// time is unfrozen it only moves on with [`advance`], which makes the
+/// displayed time and therefore every frame deterministic.
+pub fn freeze(time: DateTime<Utc>) {
+    FROZEN.with(|frozen| frozen.set(Some(time)));
+}
+
+/// Move the frozen time of the current thread forward. Without frozen time
+/// this does nothing.
+pub fn advance(duration: Duration) {
+    FROZEN.with(|frozen| frozen.set(frozen.get().map(|time| time + duration)));
+}
+
+/// Let the current thread follow the system time again
+pub fn unfreeze() {
+    FROZEN.with(|frozen| frozen.set(None));
+}
@@ -0,0 +1,635 @@
+use std::sync::Arc;
+
+use tetrotime::{simulator, Bitmap, Font, Glyph};
+
+const DIGITS: [(char, &str); 10] = [
+    (
+        '0',
+        "
+        ######
+        ######
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ######
+        ######
+        ",
+    ),
+    (
+        '1',
+        "
+        ....##
+        ....##
+        ....##
+        ....##
+        ....##
+        ....##
+        ....##
+        ....##
+        ....##
+        ....##
+        ",
+    ),
+    (
+        '2',
+        "
+        ######
+        ######
+        ....##
+        ....##
+        ######
+        ######
+        ##....
+        ##....
+        ######
+        ######
+        ",
+    ),
+    (
+        '3',
+        "
+        ######
+        ######
+        ....##
+        ....##
+        ######
+        ######
+        ....##
+        ....##
+        ######
+        ######
+        ",
+    ),
+    (
+        '4',
+        "
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ######
+        ######
+        ....##
+        ....##
+        ....##
+        ....##
+        ",
+    ),
+    (
+        '5',
+        "
+        ######
+        ######
+        ##....
+        ##....
+        ######
+        ######
+        ....##
+        ....##
+        ######
+        ######
+        ",
+    ),
+    (
+        '6',
+        "
+        ######
+        ######
+        ##....
+        ##....
+        ######
+        ######
+        ##..##
+        ##..##
+        ######
+        ######
+        ",
+    ),
+    (
+        '7',
+        "
+        ######
+        ######
+        ....##
+        ....##
+        ....##
+        ....##
+        ....##
+        ....##
+        ....##
+        ....##
+        ",
+    ),
+    (
+        '8',
+        "
+        ######
+        ######
+        ##..##
+        ##..##
+        ######
+        ######
+        ##..##
+        ##..##
+        ######
+        ######
+        ",
+    ),
+    (
+        '9',
+        "
+        ######
+        ######
+        ##..##
+        ##..##
+        ######
+        ######
+        ....##
+        ....##
+        ######
+        ######
+        ",
+    ),
+];
+
+const LETTERS: [(char, &str); 26] = [
+    (
+        'A',
+        "
+        ######
+        ######
+        ##..##
+        ##..##
+        ######
+        ######
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ",
+    ),
+    (
+        'B',
+        "
+        #####.
+        ######
+        ##..##
+        ##..##
+        #####.
+        #####.
+        ##..##
+        ##..##
+        ######
+        #####.
+        ",
+    ),
+    (
+        'C',
+        "
+        ######
+        ######
+        ##....
+        ##....
+        ##....
+        ##....
+        ##....
+        ##....
+        ######
+        ######
+        ",
+    ),
+    (
+        'D',
+        "
+        #####.
+        ######
+        ##.###
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ##.###
+        ######
+        #####.
+        ",
+    ),
+    (
+        'E',
+        "
+        ######
+        ######
+        ##....
+        ##....
+        ######
+        ######
+        ##....
+        ##....
+        ######
+        ######
+        ",
+    ),
+    (
+        'F',
+        "
+        ######
+        ######
+        ##....
+        ##....
+        ######
+        ######
+        ##....
+        ##....
+        ##....
+        ##....
+        ",
+    ),
+    (
+        'G',
+        "
+        ######
+        ######
+        ##....
+        ##....
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ######
+        ######
+        ",
+    ),
+    (
+        'H',
+        "
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ######
+        ######
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ",
+    ),
+    (
+        'I',
+        "
+        ######
+        ######
+        ..##..
+        ..##..
+        ..##..
+        ..##..
+        ..##..
+        ..##..
+        ######
+        ######
+        ",
+    ),
+    (
+        'J',
+        "
+        ....##
+        ....##
+        ....##
+        ....##
+        ....##
+        ....##
+        ##..##
+        ##..##
+        ######
+        ######
+        ",
+    ),
+    (
+        'K',
+        "
+        ##..##
+        ##..##
+        ##.###
+        #####.
+        ####..
+        ####..
+        #####.
+        ##.###
+        ##..##
+        ##..##
+        ",
+    ),
+    (
+        'L',
+        "
+        ##....
+        ##....
+        ##....
+        ##....
+        ##....
+        ##....
+        ##....
+        ##....
+        ######
+        ######
+        ",
+    ),
+    (
+        'M',
+        "
+        #....#
+        ##..##
+        ######
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ",
+    ),
+    (
+        'N',
+        "
+        ##..##
+        ###.##
+        ######
+        ##.###
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ",
+    ),
+    (
+        'O',
+        "
+        ######
+        ######
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ######
+        ######
+        ",
+    ),
+    (
+        'P',
+        "
+        ######
+        ######
+        ##..##
+        ##..##
+        ######
+        ######
+        ##....
+        ##....
+        ##....
+        ##....
+        ",
+    ),
+    (
+        'Q',
+        "
+        ######
+        ######
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ######
+        ######
+        ....##
+        ....##
+        ",
+    ),
+    (
+        'R',
+        "
+        ######
+        ######
+        ##..##
+        ##..##
+        ######
+        ######
+        ####..
+        ####..
+        ##..##
+        ##..##
+        ",
+    ),
+    (
+        'S',
+        "
+        ######
+        ######
+        ##....
+        ##....
+        ######
+        ######
+        ....##
+        ....##
+        ######
+        ######
+        ",
+    ),
+    (
+        'T',
+        "
+        ######
+        ######
+        ..##..
+        ..##..
+        ..##..
+        ..##..
+        ..##..
+        ..##..
+        ..##..
+        ..##..
+        ",
+    ),
+    (
+        'U',
+        "
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ######
+        ######
+        ",
+    ),
+    (
+        'V',
+        "
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        .####.
+        .####.
+        ..##..
+        ..##..
+        ",
+    ),
+    (
+        'W',
+        "
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ##..##
+        ######
+        ##..##
+        #....#
+        ",
+    ),
+    (
+        'X',
+        "
+        ##..##
+        ##..##
+        .#..#.
+        .####.
+        ..##..
+        ..##..
+        .####.
+        .#..#.
+        ##..##
+        ##..##
+        ",
+    ),
+    (
+        'Y',
+        "
+        ##..##
+        ##..##
+        ##..##
+        .####.
+        ..##..
+        ..##..
+        ..##..
+        ..##..
+        ..##..
+        ..##..
+        ",
+    ),
+    (
+        'Z',
+        "
+        ######
+        ######
+        ....##
+        ...###
+        ..###.
+        .###..
+        ###...
+        ##....
+        ######
+        ######
+        ",
+    ),
+];
+
+// Collect all mismatches, so a broken font shows every affected glyph at once
+fn check(font: &Font, glyphs: &[(char, &str)]) {
+    let font = Arc::new(font.clone());
+    let mismatches: Vec<String> = glyphs
+        .iter()
+        .filter_map(|&(c, art)| {
+            let expected: Bitmap = art.parse().unwrap();
+            let settled = simulator::settle(&font, Glyph::from_char(c).unwrap());
+            (settled != expected).then(|| {
+                format!(
+                    "'{}' expected\n{}\nbut settled into\n{}",
+                    c, expected, settled
+                )
+            })
+        })
+        .collect();
+    assert!(mismatches.is_empty(), "{}", mismatches.join("\n\n"));
+}
+
+#[test]
+fn digits_settle_into_their_bitmaps() {
+    check(&Font::default(), &DIGITS);
+}
+
+#[test]
+fn letters_settle_into_their_bitmaps() {
+    check(&Font::default(), &LETTERS);
+}
+
+#[test]
+fn misplaced_tetromino_is_detected() {
+    let font = Font::from_toml(
+        r#"
+        [glyphs]
+        "1" = [
+            { shape = "O", dx = 4 },
+            { shape = "L", rotation = 270, dx = 4 },
+            { shape = "I", rotation = 90, dx = 5 },
+            { shape = "J", rotation = 270, dx = 4 },
+            { shape = "O", dx = 3 },
+        ]
+        "#,
+    )
+    .unwrap();
+    let settled = simulator::settle(&Arc::new(font), Glyph::from_char('1').unwrap());
+    let expected: Bitmap = DIGITS[1].1.parse().unwrap();
+    assert_ne!(settled, expected);
+}
+
+#[test]
+fn tetromino_piling_up_too_high_widens_the_bitmap() {
+    let font = Font::from_toml(
+        r#"
+        [glyphs]
+        "1" = [
+            { shape = "I", rotation = 90, dx = 5 },
+            { shape = "I", rotation = 90, dx = 5 },
+            { shape = "I", rotation = 90, dx = 5 },
+        ]
+        "#,
+    )
+    .unwrap();
+    let settled = simulator::settle(&Arc::new(font), Glyph::from_char('1').unwrap());
+    assert_eq!((settled.width(), settled.height()), (6, 12));
+}
+
+#[test]
+fn font_file_contains_bitmaps() {
+    let font = Font::from_toml(&format!("[bitmaps]\n\"1\" = \"\"\"{}\"\"\"", DIGITS[1].1)).unwrap();
+    let one = Glyph::from_char('1').unwrap();
+    assert_eq!(font.listed_glyphs(), &[('1', one)]);
+    assert_eq!(
+        font.bitmap(one),
+        Some(&DIGITS[1].1.parse::<Bitmap>().unwrap())
+    );
+    check(&font, &DIGITS);
+}
+
+#[test]
+fn invalid_bitmaps_are_rejected() {
+    assert!("".parse::<Bitmap>().is_err());
+    assert!("##\n#".parse::<Bitmap>().is_err());
+    assert!("#x".parse::<Bitmap>().is_err());
+    assert!(Font::from_toml("[bitmaps]\n\"1\" = \"#o\"").is_err());
+}
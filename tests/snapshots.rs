@@ -0,0 +1,159 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::{TimeZone, Utc};
+use chrono_tz::Tz;
+use pixel_loop::canvas::{Canvas, InMemoryCanvas};
+use pixel_loop::color::Color;
+use tetrotime::{simulator, time, Clock, Colorscheme, Mode, Transition, Zone, ZoneLabel};
+
+const FRAME_TIME: Duration = Duration::from_millis(20);
+
+fn clock(mode: impl FnOnce() -> Mode) -> (Clock, InMemoryCanvas) {
+    clock_with(mode, |_| {})
+}
+
+// Freeze the time before the mode is created, so timers start at the same
+// point in time on every run. The settings applied by `setup` take effect
+// when the clock is centered.
+fn clock_with(
+    mode: impl FnOnce() -> Mode,
+    setup: impl FnOnce(&mut Clock),
+) -> (Clock, InMemoryCanvas) {
+    time::freeze(Utc.with_ymd_and_hms(2024, 3, 1, 12, 34, 56).unwrap());
+    let mut clock = Clock::new(mode(), Colorscheme::default());
+    setup(&mut clock);
+    let canvas = InMemoryCanvas::new(
+        clock.width() as u32 + 4,
+        clock.height() as u32 + 4,
+        &Color::from_rgb(0, 0, 0),
+    );
+    clock.center_on(canvas.width(), canvas.height());
+    (clock, canvas)
+}
+
+// Step the clock frame by frame, moving the frozen time along, and render the
+// last frame
+fn run(clock: &mut Clock, canvas: &mut InMemoryCanvas, frames: u32) {
+    for _ in 0..frames {
+        time::advance(chrono::Duration::from_std(FRAME_TIME).unwrap());
+        clock.update(canvas, FRAME_TIME);
+    }
+    canvas.clear_screen(&Color::from_rgb(0, 0, 0));
+    clock.render(canvas);
+}
+
+// Run with UPDATE_GOLDEN=1 to write the golden images after intended changes
+fn assert_golden(name: &str, canvas: &InMemoryCanvas) {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "golden", name]
+        .iter()
+        .collect::<PathBuf>()
+        .with_extension("txt");
+    let snapshot = simulator::snapshot(canvas);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, &snapshot).unwrap();
+        return;
+    }
+    let golden = fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("Missing golden image '{}'", path.display()));
+    assert!(
+        snapshot == golden,
+        "'{}' differs from the golden image:\n{}",
+        name,
+        snapshot
+    );
+}
+
+#[test]
+fn text_settles() {
+    let (mut clock, mut canvas) = clock(|| Mode::Text("HI!".to_string()));
+    run(&mut clock, &mut canvas, 400);
+    assert_golden("text", &canvas);
+}
+
+#[test]
+fn world_clock_drops_in() {
+    let (mut clock, mut canvas) = clock(|| {
+        Mode::WorldClock(
+            "%H:%M".to_string(),
+            vec![Zone::new(Tz::Europe__Berlin, ZoneLabel::None)],
+        )
+    });
+    run(&mut clock, &mut canvas, 30);
+    assert_golden("world_clock_falling", &canvas);
+    run(&mut clock, &mut canvas, 400);
+    assert_golden("world_clock", &canvas);
+}
+
+#[test]
+fn stopwatch_counts() {
+    let (mut clock, mut canvas) = clock(Mode::stopwatch);
+    run(&mut clock, &mut canvas, 150);
+    assert_golden("stopwatch", &canvas);
+}
+
+#[test]
+fn countdown_clears_lines() {
+    let (mut clock, mut canvas) = clock_with(
+        || Mode::countdown(chrono::Duration::seconds(90)),
+        |clock| clock.set_transition(Transition::LineClear),
+    );
+    run(&mut clock, &mut canvas, 400);
+    assert_golden("countdown", &canvas);
+    run(&mut clock, &mut canvas, 55);
+    assert_golden("countdown_line_clear", &canvas);
+}
+
+// Settle the text at the given scale, placed two cells away from the top
+// left corner of a canvas with two cells of margin on every side
+fn settled_text(scale: i64) -> InMemoryCanvas {
+    let (mut clock, _) = clock_with(
+        || Mode::Text("HI!".to_string()),
+        |clock| clock.set_scale(scale),
+    );
+    let mut canvas = InMemoryCanvas::new(
+        (clock.width() + 4 * scale) as u32,
+        (clock.height() + 4 * scale) as u32,
+        &Color::from_rgb(0, 0, 0),
+    );
+    clock.set_position(2 * scale, clock.height() + 2 * scale);
+    run(&mut clock, &mut canvas, 400);
+    canvas
+}
+
+#[test]
+fn scale_renders_every_cell_as_a_block() {
+    let small = settled_text(1);
+    let large = settled_text(2);
+    assert_eq!(large.width(), 2 * small.width());
+    assert_eq!(large.height(), 2 * small.height());
+    for y in 0..large.height() {
+        for x in 0..large.width() {
+            assert!(
+                large.get(x, y) == small.get(x / 2, y / 2),
+                "pixel {}x{} differs",
+                x,
+                y
+            );
+        }
+    }
+    assert!(simulator::snapshot(&small).contains('a'));
+}
+
+#[test]
+fn auto_fit_picks_the_largest_scale_that_fits() {
+    let (clock, _) = clock(|| Mode::Text("HI!".to_string()));
+    // The clock keeps a margin of two cells on every side
+    let (width, height) = (clock.width() as u32 + 4, clock.height() as u32 + 4);
+    assert_eq!(clock.fit_scale(width, height), 1);
+    assert_eq!(clock.fit_scale(3 * width, 3 * height), 3);
+    assert_eq!(clock.fit_scale(3 * width - 1, 3 * height), 2);
+    assert_eq!(clock.fit_scale(3 * width, 3 * height - 1), 2);
+    assert_eq!(clock.fit_scale(10 * width, 3 * height), 3);
+    assert_eq!(clock.fit_scale(width / 2, height / 2), 1);
+
+    let (clock, _) = clock_with(|| Mode::Text("HI!".to_string()), |clock| clock.set_scale(3));
+    assert!(clock.width() + 4 * 3 <= 3 * width as i64);
+    assert!(clock.height() + 4 * 3 <= 3 * height as i64);
+}
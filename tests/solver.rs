@@ -0,0 +1,68 @@
+use std::sync::Arc;
+
+use tetrotime::{simulator, solver, Bitmap, Font, Glyph};
+
+// The solution has to settle into the bitmap when dropped like on screen
+fn assert_solves(c: char, target: &Bitmap) {
+    let glyph = Glyph::from_char(c).unwrap();
+    let animation = solver::solve(target).unwrap_or_else(|| panic!("'{}' has no solution", c));
+    let mut font = Font::default();
+    font.set_animation(glyph, animation);
+    assert_eq!(
+        &simulator::settle(&Arc::new(font), glyph),
+        target,
+        "'{}' settled differently",
+        c
+    );
+}
+
+#[test]
+fn builtin_glyphs_are_solved() {
+    let font = Arc::new(Font::default());
+    for c in ('0'..='9').chain('A'..='Z') {
+        let target = simulator::settle(&font, Glyph::from_char(c).unwrap());
+        assert_solves(c, &target);
+    }
+}
+
+#[test]
+fn new_design_is_solved() {
+    let target: Bitmap = "
+        ######
+        ######
+        ....##
+        ...##.
+        ...##.
+        ..##..
+        ..##..
+        .##...
+        .##...
+        ##....
+    "
+    .parse()
+    .unwrap();
+    assert_solves('7', &target);
+}
+
+#[test]
+fn cell_count_not_divisible_by_four_has_no_solution() {
+    let target: Bitmap = "
+        ###.
+        ####
+    "
+    .parse()
+    .unwrap();
+    assert!(solver::solve(&target).is_none());
+}
+
+#[test]
+fn floating_cells_have_no_solution() {
+    let target: Bitmap = "
+        ####
+        ....
+        ####
+    "
+    .parse()
+    .unwrap();
+    assert!(solver::solve(&target).is_none());
+}